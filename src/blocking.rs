@@ -0,0 +1,256 @@
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use core_futures_io::AsyncRead;
+use futures::{channel::oneshot, ready, Future};
+use std::{
+    io::{self, Read},
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex, OnceLock,
+    },
+    thread,
+};
+use thiserror::Error;
+
+pub type Offload = Arc<dyn Fn(Box<dyn FnOnce() + Send>) + Send + Sync>;
+
+pub fn thread_offload() -> Offload {
+    Arc::new(|task| {
+        thread::spawn(task);
+    })
+}
+
+type Task = Box<dyn FnOnce() + Send>;
+
+struct Counters {
+    idle: AtomicUsize,
+    queued: AtomicUsize,
+    spawned: AtomicUsize,
+}
+
+fn worker(receiver: Arc<Mutex<mpsc::Receiver<Task>>>, counters: Arc<Counters>) {
+    loop {
+        counters.idle.fetch_add(1, Ordering::SeqCst);
+        let task = receiver.lock().unwrap().recv();
+        counters.idle.fetch_sub(1, Ordering::SeqCst);
+
+        match task {
+            Ok(task) => {
+                counters.queued.fetch_sub(1, Ordering::SeqCst);
+                let _ = catch_unwind(AssertUnwindSafe(task));
+            }
+            Err(_) => return,
+        }
+    }
+}
+
+pub fn pool_offload(threads: usize) -> Offload {
+    let (sender, receiver) = mpsc::channel::<Task>();
+    let receiver = Arc::new(Mutex::new(receiver));
+    let counters = Arc::new(Counters {
+        idle: AtomicUsize::new(0),
+        queued: AtomicUsize::new(0),
+        spawned: AtomicUsize::new(0),
+    });
+    let threads = threads.max(1);
+
+    Arc::new(move |task| {
+        let queued = counters.queued.fetch_add(1, Ordering::SeqCst) + 1;
+
+        if sender.send(task).is_err() {
+            return;
+        }
+
+        if queued > counters.idle.load(Ordering::SeqCst)
+            && counters
+                .spawned
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |spawned| {
+                    if spawned < threads {
+                        Some(spawned + 1)
+                    } else {
+                        None
+                    }
+                })
+                .is_ok()
+        {
+            let (receiver, counters) = (receiver.clone(), counters.clone());
+
+            thread::spawn(move || worker(receiver, counters));
+        }
+    })
+}
+
+pub fn default_offload() -> Offload {
+    static DEFAULT: OnceLock<Offload> = OnceLock::new();
+
+    DEFAULT
+        .get_or_init(|| {
+            pool_offload(
+                thread::available_parallelism()
+                    .map(usize::from)
+                    .unwrap_or(4),
+            )
+        })
+        .clone()
+}
+
+#[derive(Debug, Error)]
+#[error("offloaded task was dropped before completing")]
+pub struct Dropped;
+
+impl From<Dropped> for io::Error {
+    fn from(input: Dropped) -> Self {
+        io::Error::new(io::ErrorKind::Other, input)
+    }
+}
+
+pub(crate) fn offload<T: Send + 'static, F: FnOnce() -> T + Send + 'static>(
+    offload: Offload,
+    task: F,
+) -> impl Future<Output = Result<T, Dropped>> + Send {
+    async move {
+        let (sender, receiver) = oneshot::channel();
+
+        (offload)(Box::new(move || {
+            let _ = sender.send(task());
+        }));
+
+        receiver.await.map_err(|_| Dropped)
+    }
+}
+
+type Chunk<R> = (R, io::Result<Vec<u8>>);
+
+pub struct OffloadReader<R> {
+    offload: Offload,
+    reader: Option<R>,
+    pending: Option<oneshot::Receiver<Chunk<R>>>,
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+impl<R: Read + Send + 'static> OffloadReader<R> {
+    pub fn new(offload: Offload, reader: R) -> Self {
+        OffloadReader {
+            offload,
+            reader: Some(reader),
+            pending: None,
+            buffer: vec![],
+            position: 0,
+        }
+    }
+}
+
+impl<R> Unpin for OffloadReader<R> {}
+
+impl<R: Read + Send + 'static> AsyncRead for OffloadReader<R> {
+    type Error = io::Error;
+
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buffer: &mut [u8],
+    ) -> Poll<Result<usize, Self::Error>> {
+        let this = &mut *self;
+
+        if buffer.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        loop {
+            if this.position < this.buffer.len() {
+                let remaining = &this.buffer[this.position..];
+                let len = remaining.len().min(buffer.len());
+
+                buffer[..len].copy_from_slice(&remaining[..len]);
+                this.position += len;
+
+                return Poll::Ready(Ok(len));
+            }
+
+            if this.pending.is_none() {
+                let mut reader = this.reader.take().ok_or(Dropped)?;
+                let len = buffer.len();
+                let (sender, receiver) = oneshot::channel();
+
+                (this.offload)(Box::new(move || {
+                    let mut data = vec![0u8; len];
+                    let result = reader.read(&mut data).map(|read| {
+                        data.truncate(read);
+                        data
+                    });
+                    let _ = sender.send((reader, result));
+                }));
+
+                this.pending = Some(receiver);
+            }
+
+            let chunk = ready!(Pin::new(this.pending.as_mut().unwrap()).poll(cx));
+            this.pending = None;
+
+            let (reader, result) = chunk.map_err(|_| Dropped)?;
+            this.reader = Some(reader);
+
+            let data = result?;
+
+            if data.is_empty() {
+                return Poll::Ready(Ok(0));
+            }
+
+            this.buffer = data;
+            this.position = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use std::{collections::HashSet, sync::Barrier};
+
+    #[test]
+    fn pool_reuses_a_bounded_set_of_threads() {
+        let pool = pool_offload(2);
+
+        let threads = (0..16)
+            .map(|_| offload(pool.clone(), || thread::current().id()))
+            .collect::<Vec<_>>();
+        let threads = threads
+            .into_iter()
+            .map(|task| block_on(task).unwrap())
+            .collect::<HashSet<_>>();
+
+        assert!(threads.len() <= 2);
+    }
+
+    #[test]
+    fn pool_survives_panicking_tasks() {
+        let pool = pool_offload(1);
+
+        assert!(block_on(offload(pool.clone(), || -> u8 { panic!("task") })).is_err());
+        assert_eq!(block_on(offload(pool, || 1)).unwrap(), 1);
+    }
+
+    #[test]
+    fn pool_runs_tasks_concurrently() {
+        let pool = pool_offload(2);
+        let barrier = Arc::new(Barrier::new(2));
+
+        let tasks = (0..2)
+            .map(|_| {
+                let barrier = barrier.clone();
+                offload(pool.clone(), move || {
+                    barrier.wait();
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for task in tasks {
+            block_on(task).unwrap();
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use crate::{
+    resource::{
+        graph::{Closure, ClosureError, Link},
+        hash::{Algorithm, Digest, Hasher},
+        manager::{ProviderMetadata, ResourceManager, ResourceManagerExt, ResourceRegistrant},
+        provider::ResourceProvider,
+    },
+    SimpleProviderHandle, SimpleResourceManager,
+};
+use core::{any::Any, hash::Hash, marker::PhantomData};
+use futures::{
+    future::{ready, Ready},
+    Future,
+};
+use protocol::allocated::ProtocolError;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tar::{Archive, Builder, Header};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum BundleError {
+    #[error("io error: {0}")]
+    Io(#[source] io::Error),
+    #[error("hash mismatch for bundled resource")]
+    Mismatch,
+    #[error("closure error: {0}")]
+    Closure(#[source] ClosureError),
+    #[error("registration error: {0}")]
+    Register(#[source] ProtocolError),
+    #[error("closure is missing resources: {0:?}")]
+    Incomplete(Vec<String>),
+}
+
+impl From<io::Error> for BundleError {
+    fn from(input: io::Error) -> Self {
+        BundleError::Io(input)
+    }
+}
+
+pub struct BundleProvider<A: Algorithm, H: Hasher<A>> {
+    path: Arc<PathBuf>,
+    entries: Arc<HashMap<A::Hash, (u64, u64)>>,
+    hasher: PhantomData<fn() -> H>,
+}
+
+impl<A: Algorithm, H: Hasher<A>> Clone for BundleProvider<A, H> {
+    fn clone(&self) -> Self {
+        BundleProvider {
+            path: self.path.clone(),
+            entries: self.entries.clone(),
+            hasher: PhantomData,
+        }
+    }
+}
+
+impl<A: Algorithm, H: Hasher<A>> BundleProvider<A, H>
+where
+    A::Hash: Digest + Eq + Hash,
+{
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<Self, BundleError> {
+        let path = path.into();
+        let mut entries = HashMap::new();
+
+        let mut archive = Archive::new(File::open(&path)?);
+
+        for entry in archive.entries()? {
+            let entry = entry?;
+
+            if let Some(hash) = entry
+                .path()?
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(A::Hash::from_hex)
+            {
+                entries.insert(hash, (entry.raw_file_position(), entry.size()));
+            }
+        }
+
+        Ok(BundleProvider {
+            path: Arc::new(path),
+            entries: Arc::new(entries),
+            hasher: PhantomData,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn hashes(&self) -> impl Iterator<Item = &A::Hash> {
+        self.entries.keys()
+    }
+
+    fn read(&self, hash: &A::Hash) -> Result<Option<Vec<u8>>, BundleError> {
+        let (offset, len) = match self.entries.get(hash) {
+            Some(entry) => *entry,
+            None => return Ok(None),
+        };
+
+        let mut file = File::open(&*self.path)?;
+        file.seek(SeekFrom::Start(offset))?;
+
+        let mut data = vec![0u8; len as usize];
+        file.read_exact(&mut data)?;
+
+        let mut hasher = H::new();
+        hasher.write(&data);
+
+        if &hasher.hash() != hash {
+            return Err(BundleError::Mismatch);
+        }
+
+        Ok(Some(data))
+    }
+}
+
+impl<A: Algorithm, H: Hasher<A>> ResourceProvider<A> for BundleProvider<A, H>
+where
+    A::Hash: Digest + Eq + Hash,
+{
+    type Fetch = Ready<Result<Option<Vec<u8>>, BundleError>>;
+
+    fn fetch(&self, hash: A::Hash) -> Self::Fetch {
+        ready(self.read(&hash))
+    }
+}
+
+pub struct BundleWriter<W: Write> {
+    builder: Builder<W>,
+}
+
+impl<W: Write> BundleWriter<W> {
+    pub fn new(writer: W) -> Self {
+        BundleWriter {
+            builder: Builder::new(writer),
+        }
+    }
+
+    pub fn append<D: Digest>(&mut self, hash: &D, data: &[u8]) -> Result<(), BundleError> {
+        let mut header = Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+
+        self.builder.append_data(&mut header, hash.to_hex(), data)?;
+
+        Ok(())
+    }
+
+    pub fn append_closure<A: Algorithm>(&mut self, closure: &Closure<A>) -> Result<(), BundleError>
+    where
+        A::Hash: Digest,
+    {
+        for (hash, data) in &closure.resources {
+            self.append(hash, data)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<W, BundleError> {
+        Ok(self.builder.into_inner()?)
+    }
+}
+
+pub fn export<'a, M: ResourceManager + Sync, A: Algorithm + Any, W: Write + Send + 'a>(
+    manager: &'a M,
+    roots: Vec<Link<A>>,
+    writer: W,
+) -> impl Future<Output = Result<W, BundleError>> + 'a
+where
+    M::Fetch: Send + 'static,
+    A::Hash: Digest + Eq + Hash + Clone + Send + 'static,
+{
+    let closure = manager.fetch_closure(roots);
+
+    async move {
+        let closure = closure.await.map_err(BundleError::Closure)?;
+
+        if !closure.missing.is_empty() {
+            return Err(BundleError::Incomplete(
+                closure.missing.iter().map(Digest::to_hex).collect(),
+            ));
+        }
+
+        let mut bundle = BundleWriter::new(writer);
+        bundle.append_closure(&closure)?;
+        bundle.finish()
+    }
+}
+
+impl SimpleResourceManager {
+    pub fn mount_bundle<A: Algorithm + Send + 'static, H: Hasher<A> + 'static, P: Into<PathBuf>>(
+        &mut self,
+        path: P,
+    ) -> impl Future<Output = Result<SimpleProviderHandle, BundleError>>
+    where
+        A::Hash: Digest + Eq + Hash + Send + Sync + 'static,
+    {
+        let path = path.into();
+        let metadata = ProviderMetadata::named(path.display().to_string()).read_only(true);
+        let provider = BundleProvider::<A, H>::open(path);
+        let mut manager = self.clone();
+
+        async move {
+            ResourceRegistrant::<A, _>::register_provider(&mut manager, provider?, metadata)
+                .await
+                .map_err(BundleError::Register)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        resource::{hash::HasherExt, store::ResourceStore},
+        test_util::{temp_dir, TestHasher},
+        MemoryStore, Sha256, Sha256Sum,
+    };
+    use futures::executor::block_on;
+
+    fn manager(store: &MemoryStore<Sha256>) -> SimpleResourceManager {
+        let mut manager = SimpleResourceManager::new();
+
+        block_on(ResourceRegistrant::<Sha256, _>::register_provider(
+            &mut manager,
+            store.clone(),
+            ProviderMetadata::default(),
+        ))
+        .unwrap();
+
+        manager
+    }
+
+    fn hash(data: &[u8]) -> Sha256Sum {
+        <TestHasher as HasherExt<Sha256>>::hash(data.to_vec())
+    }
+
+    #[test]
+    fn export_round_trips_through_provider() {
+        let store = MemoryStore::<Sha256>::new();
+        block_on(store.put_raw(hash(b"data"), b"data".to_vec())).unwrap();
+
+        let manager = manager(&store);
+        let path = temp_dir("bundle-export").join("bundle.tar");
+
+        let file = block_on(export(
+            &manager,
+            vec![Link::leaf(hash(b"data"))],
+            File::create(&path).unwrap(),
+        ))
+        .unwrap();
+        drop(file);
+
+        let provider = BundleProvider::<Sha256, TestHasher>::open(&path).unwrap();
+
+        assert_eq!(
+            block_on(provider.fetch(hash(b"data"))).unwrap(),
+            Some(b"data".to_vec())
+        );
+    }
+
+    #[test]
+    fn export_rejects_incomplete_closures() {
+        let store = MemoryStore::<Sha256>::new();
+        block_on(store.put_raw(hash(b"data"), b"data".to_vec())).unwrap();
+
+        let manager = manager(&store);
+
+        let result = block_on(export(
+            &manager,
+            vec![Link::leaf(hash(b"data")), Link::leaf(hash(b"missing"))],
+            vec![],
+        ));
+
+        match result {
+            Err(BundleError::Incomplete(missing)) => {
+                assert_eq!(missing, vec![hash(b"missing").to_hex()])
+            }
+            _ => panic!("expected an incomplete closure"),
+        }
+    }
+}
@@ -0,0 +1,391 @@
+use crate::{
+    keyed::{Key, KeyedFetch, Keys},
+    resource::{
+        hash::{Algorithm, Digest},
+        manager::ResourceManager,
+        ResourceError,
+    },
+};
+use futures::{Future, TryFutureExt};
+use std::{
+    any::{Any, TypeId},
+    collections::{BTreeMap, HashMap, VecDeque},
+    convert::Infallible,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub negative_hits: u64,
+    pub evictions: u64,
+    pub entries: usize,
+    pub bytes: usize,
+}
+
+struct Entry {
+    data: Vec<u8>,
+    tick: u64,
+}
+
+struct Cache {
+    budget: usize,
+    negative_ttl: Option<Duration>,
+    tick: u64,
+    entries: HashMap<Key, Entry>,
+    order: BTreeMap<u64, Key>,
+    negative: HashMap<Key, Instant>,
+    expiries: VecDeque<(Instant, Key)>,
+    negative_bytes: usize,
+    stats: CacheStats,
+}
+
+impl Cache {
+    fn get(&mut self, key: &Key) -> Option<Option<Vec<u8>>> {
+        self.tick += 1;
+        let tick = self.tick;
+
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.order.remove(&entry.tick);
+            self.order.insert(tick, key.clone());
+            entry.tick = tick;
+            self.stats.hits += 1;
+            return Some(Some(entry.data.clone()));
+        }
+
+        if let Some(expiry) = self.negative.get(key) {
+            if *expiry > Instant::now() {
+                self.stats.negative_hits += 1;
+                return Some(None);
+            }
+            self.remove_negative(key);
+        }
+
+        self.stats.misses += 1;
+        None
+    }
+
+    fn used(&self) -> usize {
+        self.stats.bytes + self.negative_bytes
+    }
+
+    fn insert(&mut self, key: Key, data: Vec<u8>) {
+        self.remove_negative(&key);
+        self.remove(&key);
+
+        if data.len() > self.budget {
+            return;
+        }
+
+        self.tick += 1;
+        self.stats.bytes += data.len();
+        self.stats.entries += 1;
+        self.order.insert(self.tick, key.clone());
+        self.entries.insert(
+            key,
+            Entry {
+                data,
+                tick: self.tick,
+            },
+        );
+
+        while self.used() > self.budget {
+            if !self.expiries.is_empty() {
+                self.pop_negative();
+                continue;
+            }
+
+            let oldest = match self.order.keys().next() {
+                Some(tick) => *tick,
+                None => break,
+            };
+
+            if let Some(key) = self.order.remove(&oldest) {
+                self.remove(&key);
+                self.stats.evictions += 1;
+            }
+        }
+    }
+
+    fn insert_negative(&mut self, key: Key) {
+        let ttl = match self.negative_ttl {
+            Some(ttl) => ttl,
+            None => return,
+        };
+
+        let now = Instant::now();
+
+        while let Some((expiry, _)) = self.expiries.front() {
+            if *expiry > now {
+                break;
+            }
+            self.pop_negative();
+        }
+
+        let len = key.1.len();
+
+        if self.stats.bytes + len > self.budget {
+            return;
+        }
+
+        if self.negative.insert(key.clone(), now + ttl).is_none() {
+            self.negative_bytes += len;
+        }
+        self.expiries.push_back((now + ttl, key));
+
+        while self.used() > self.budget {
+            self.pop_negative();
+        }
+    }
+
+    fn pop_negative(&mut self) {
+        if let Some((expiry, key)) = self.expiries.pop_front() {
+            if self.negative.get(&key) == Some(&expiry) {
+                self.remove_negative(&key);
+            }
+        }
+    }
+
+    fn remove_negative(&mut self, key: &Key) {
+        if self.negative.remove(key).is_some() {
+            self.negative_bytes -= key.1.len();
+        }
+    }
+
+    fn remove(&mut self, key: &Key) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.order.remove(&entry.tick);
+            self.stats.bytes -= entry.data.len();
+            self.stats.entries -= 1;
+        }
+    }
+}
+
+pub struct CachingResourceManager<M: ResourceManager> {
+    manager: M,
+    keys: Keys,
+    cache: Arc<Mutex<Cache>>,
+}
+
+impl<M: ResourceManager> CachingResourceManager<M> {
+    pub fn new(manager: M, budget: usize) -> Self {
+        CachingResourceManager {
+            manager,
+            keys: Keys::default(),
+            cache: Arc::new(Mutex::new(Cache {
+                budget,
+                negative_ttl: None,
+                tick: 0,
+                entries: HashMap::new(),
+                order: BTreeMap::new(),
+                negative: HashMap::new(),
+                expiries: VecDeque::new(),
+                negative_bytes: 0,
+                stats: CacheStats::default(),
+            })),
+        }
+    }
+
+    pub fn algorithm<A: Algorithm + Any>(mut self) -> Self
+    where
+        A::Hash: Digest + 'static,
+    {
+        self.keys.register::<A>();
+        self
+    }
+
+    pub fn negative_ttl(self, ttl: Duration) -> Self {
+        self.cache.lock().unwrap().negative_ttl = Some(ttl);
+        self
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.cache.lock().unwrap().stats
+    }
+
+    pub fn clear(&self) {
+        let mut cache = self.cache.lock().unwrap();
+
+        cache.entries.clear();
+        cache.order.clear();
+        cache.negative.clear();
+        cache.expiries.clear();
+        cache.negative_bytes = 0;
+        cache.stats.entries = 0;
+        cache.stats.bytes = 0;
+    }
+}
+
+impl<M: ResourceManager> ResourceManager for CachingResourceManager<M>
+where
+    M::Fetch: Send + 'static,
+{
+    type Fetch = KeyedFetch;
+
+    fn fetch(
+        &self,
+        algo: TypeId,
+        hash: Box<dyn FnMut() -> Box<dyn Any + Send> + Send>,
+    ) -> Self::Fetch {
+        self.keys.fetch(&self.manager, algo, hash, |key, hash| {
+            if let Some(cached) = self.cache.lock().unwrap().get(&key) {
+                return Box::pin(async move { Ok(cached) });
+            }
+
+            let fetch = self.manager.fetch(algo, hash);
+            let cache = self.cache.clone();
+
+            Box::pin(async move {
+                let data = fetch.await?;
+
+                let mut cache = cache.lock().unwrap();
+                match &data {
+                    Some(data) => cache.insert(key, data.clone()),
+                    None => cache.insert_negative(key),
+                }
+
+                Ok(data)
+            })
+        })
+    }
+
+    fn publish(
+        &self,
+        algo: TypeId,
+        mut hash: Box<dyn FnMut() -> Box<dyn Any + Send> + Send>,
+        data: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), ResourceError<Infallible>>> + Send>> {
+        let key = self.keys.key(algo, &mut hash);
+        let cache = self.cache.clone();
+        let cached = key.as_ref().map(|_| data.clone());
+
+        Box::pin(self.manager.publish(algo, hash, data).map_ok(move |()| {
+            if let (Some(key), Some(data)) = (key, cached) {
+                cache.lock().unwrap().insert(key, data);
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        resource::{
+            manager::{ProviderMetadata, ResourceRegistrant},
+            store::ResourceStore,
+        },
+        MemoryStore, Sha256, Sha256Sum, SimpleResourceManager,
+    };
+    use futures::executor::block_on;
+
+    fn cached(
+        store: &MemoryStore<Sha256>,
+        budget: usize,
+    ) -> CachingResourceManager<SimpleResourceManager> {
+        let mut manager = SimpleResourceManager::new();
+
+        block_on(ResourceRegistrant::<Sha256, _>::register_provider(
+            &mut manager,
+            store.clone(),
+            ProviderMetadata::default(),
+        ))
+        .unwrap();
+
+        CachingResourceManager::new(manager, budget).algorithm::<Sha256>()
+    }
+
+    fn fetch(
+        manager: &CachingResourceManager<SimpleResourceManager>,
+        hash: Sha256Sum,
+    ) -> Option<Vec<u8>> {
+        block_on(manager.fetch(
+            TypeId::of::<Sha256>(),
+            Box::new(move || Box::new(hash) as Box<dyn Any + Send>),
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let store = MemoryStore::<Sha256>::new();
+        let (a, b, c) = (Sha256Sum([1; 32]), Sha256Sum([2; 32]), Sha256Sum([3; 32]));
+
+        for hash in &[a, b, c] {
+            block_on(store.put_raw(*hash, vec![hash.0[0]; 4])).unwrap();
+        }
+
+        let manager = cached(&store, 8);
+
+        fetch(&manager, a);
+        fetch(&manager, b);
+        fetch(&manager, a);
+        fetch(&manager, c);
+
+        let stats = manager.stats();
+        assert_eq!((stats.entries, stats.bytes, stats.evictions), (2, 8, 1));
+
+        for hash in &[a, b, c] {
+            block_on(store.remove(*hash)).unwrap();
+        }
+
+        assert_eq!(fetch(&manager, a), Some(vec![1; 4]));
+        assert_eq!(fetch(&manager, c), Some(vec![3; 4]));
+        assert_eq!(fetch(&manager, b), None);
+    }
+
+    #[test]
+    fn sweeps_expired_negatives() {
+        let manager = cached(&MemoryStore::new(), 1024).negative_ttl(Duration::from_secs(0));
+
+        for byte in 0..16 {
+            fetch(&manager, Sha256Sum([byte; 32]));
+        }
+
+        assert!(manager.cache.lock().unwrap().negative.len() <= 1);
+    }
+
+    #[test]
+    fn negatives_share_the_budget() {
+        let store = MemoryStore::<Sha256>::new();
+        let hit = Sha256Sum([16; 32]);
+        block_on(store.put_raw(hit, vec![0; 8])).unwrap();
+
+        let manager = cached(&store, 64).negative_ttl(Duration::from_secs(3600));
+
+        fetch(&manager, Sha256Sum([0; 32]));
+        fetch(&manager, Sha256Sum([1; 32]));
+        fetch(&manager, hit);
+
+        let cache = manager.cache.lock().unwrap();
+        assert_eq!((cache.negative.len(), cache.stats.entries), (1, 1));
+        assert!(cache.used() <= 64);
+        drop(cache);
+
+        fetch(&manager, Sha256Sum([2; 32]));
+
+        let cache = manager.cache.lock().unwrap();
+        assert_eq!((cache.negative.len(), cache.stats.entries), (1, 1));
+        assert_eq!(cache.used(), 40);
+    }
+
+    #[test]
+    fn bounds_negatives_by_budget() {
+        let manager = cached(&MemoryStore::new(), 64).negative_ttl(Duration::from_secs(3600));
+
+        for byte in 0..16 {
+            fetch(&manager, Sha256Sum([byte; 32]));
+        }
+
+        let cache = manager.cache.lock().unwrap();
+        assert_eq!(cache.negative.len(), 2);
+        assert_eq!(cache.negative_bytes, 64);
+        drop(cache);
+
+        fetch(&manager, Sha256Sum([15; 32]));
+        assert_eq!(manager.stats().negative_hits, 1);
+    }
+}
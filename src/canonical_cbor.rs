@@ -0,0 +1,346 @@
+use crate::resource::{domain::Domain, Rehydrate};
+use futures::future::{ready, Ready};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_cbor::{
+    from_slice,
+    value::{from_value, to_value},
+    Error as CborError, Value,
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum CanonicalCborError {
+    #[error("cbor error: {0}")]
+    Cbor(#[source] CborError),
+    #[error("input is not in canonical form")]
+    NonCanonical,
+    #[error("integer out of range")]
+    IntegerRange,
+    #[error("unsupported value")]
+    Unsupported,
+}
+
+impl From<CborError> for CanonicalCborError {
+    fn from(input: CborError) -> Self {
+        CanonicalCborError::Cbor(input)
+    }
+}
+
+fn head(buffer: &mut Vec<u8>, major: u8, value: u64) {
+    let major = major << 5;
+
+    if value < 24 {
+        buffer.push(major | value as u8);
+    } else if value <= u8::MAX as u64 {
+        buffer.push(major | 24);
+        buffer.push(value as u8);
+    } else if value <= u16::MAX as u64 {
+        buffer.push(major | 25);
+        buffer.extend_from_slice(&(value as u16).to_be_bytes());
+    } else if value <= u32::MAX as u64 {
+        buffer.push(major | 26);
+        buffer.extend_from_slice(&(value as u32).to_be_bytes());
+    } else {
+        buffer.push(major | 27);
+        buffer.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+fn half(value: f32) -> Option<u16> {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+        return if mantissa == 0 {
+            Some(sign | 0x7c00)
+        } else {
+            None
+        };
+    }
+
+    if exponent == 0 {
+        return if mantissa == 0 { Some(sign) } else { None };
+    }
+
+    let exponent = exponent - 127;
+
+    if exponent > 15 {
+        None
+    } else if exponent >= -14 {
+        if mantissa & 0x1fff != 0 {
+            None
+        } else {
+            Some(sign | (((exponent + 15) as u16) << 10) | (mantissa >> 13) as u16)
+        }
+    } else {
+        let full = mantissa | 0x80_0000;
+        let shift = -(exponent + 1) as u32;
+
+        if shift > 24 || full & ((1 << shift) - 1) != 0 {
+            None
+        } else {
+            Some(sign | (full >> shift) as u16)
+        }
+    }
+}
+
+fn float(buffer: &mut Vec<u8>, value: f64) {
+    if value.is_nan() {
+        buffer.extend_from_slice(&[0xf9, 0x7e, 0x00]);
+        return;
+    }
+
+    let single = value as f32;
+
+    if single as f64 == value {
+        if let Some(half) = half(single) {
+            buffer.push(0xf9);
+            buffer.extend_from_slice(&half.to_be_bytes());
+        } else {
+            buffer.push(0xfa);
+            buffer.extend_from_slice(&single.to_bits().to_be_bytes());
+        }
+    } else {
+        buffer.push(0xfb);
+        buffer.extend_from_slice(&value.to_bits().to_be_bytes());
+    }
+}
+
+fn encode(buffer: &mut Vec<u8>, value: &Value) -> Result<(), CanonicalCborError> {
+    match value {
+        Value::Null => buffer.push(0xf6),
+        Value::Bool(false) => buffer.push(0xf4),
+        Value::Bool(true) => buffer.push(0xf5),
+        Value::Integer(value) => {
+            let value = *value;
+
+            if value >= 0 {
+                if value > u64::MAX as i128 {
+                    return Err(CanonicalCborError::IntegerRange);
+                }
+                head(buffer, 0, value as u64);
+            } else {
+                let value = -1 - value;
+                if value > u64::MAX as i128 {
+                    return Err(CanonicalCborError::IntegerRange);
+                }
+                head(buffer, 1, value as u64);
+            }
+        }
+        Value::Float(value) => float(buffer, *value),
+        Value::Bytes(bytes) => {
+            head(buffer, 2, bytes.len() as u64);
+            buffer.extend_from_slice(bytes);
+        }
+        Value::Text(text) => {
+            head(buffer, 3, text.len() as u64);
+            buffer.extend_from_slice(text.as_bytes());
+        }
+        Value::Array(items) => {
+            head(buffer, 4, items.len() as u64);
+            for item in items {
+                encode(buffer, item)?;
+            }
+        }
+        Value::Map(entries) => {
+            let mut encoded = entries
+                .iter()
+                .map(|(key, value)| {
+                    let mut key_buffer = vec![];
+                    encode(&mut key_buffer, key)?;
+                    Ok((key_buffer, value))
+                })
+                .collect::<Result<Vec<_>, CanonicalCborError>>()?;
+
+            encoded.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            head(buffer, 5, encoded.len() as u64);
+            for (key, value) in encoded {
+                buffer.extend_from_slice(&key);
+                encode(buffer, value)?;
+            }
+        }
+        Value::Tag(tag, value) => {
+            head(buffer, 6, *tag);
+            encode(buffer, value)?;
+        }
+        _ => return Err(CanonicalCborError::Unsupported),
+    }
+
+    Ok(())
+}
+
+fn to_canonical<T: Serialize>(data: T) -> Result<Vec<u8>, CanonicalCborError> {
+    let mut buffer = vec![];
+    encode(&mut buffer, &to_value(data)?)?;
+    Ok(buffer)
+}
+
+fn from_canonical<T: DeserializeOwned>(data: Vec<u8>) -> Result<T, CanonicalCborError> {
+    let value: Value = from_slice(&data)?;
+
+    let mut buffer = Vec::with_capacity(data.len());
+    encode(&mut buffer, &value)?;
+
+    if buffer != data {
+        return Err(CanonicalCborError::NonCanonical);
+    }
+
+    Ok(from_value(value)?)
+}
+
+pub struct CanonicalCbor;
+
+impl<T: DeserializeOwned + Serialize> Rehydrate<T> for CanonicalCbor {
+    type RehydrateError = CanonicalCborError;
+    type Rehydrate = Ready<Result<T, Self::RehydrateError>>;
+    type DumpError = CanonicalCborError;
+    type Dump = Ready<Result<Vec<u8>, Self::DumpError>>;
+
+    fn rehydrate(data: Vec<u8>) -> Self::Rehydrate {
+        ready(from_canonical(data))
+    }
+    fn dump(data: T) -> Self::Dump {
+        ready(to_canonical(data))
+    }
+}
+
+impl Domain for CanonicalCbor {
+    const TAG: &'static str = "canonical-cbor";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use std::collections::{BTreeMap, HashMap};
+
+    fn dump<T: DeserializeOwned + Serialize>(data: T) -> Vec<u8> {
+        block_on(<CanonicalCbor as Rehydrate<T>>::dump(data)).unwrap()
+    }
+
+    fn rehydrate<T: DeserializeOwned + Serialize>(data: Vec<u8>) -> Result<T, CanonicalCborError> {
+        block_on(<CanonicalCbor as Rehydrate<T>>::rehydrate(data))
+    }
+
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    #[test]
+    fn map_insertion_order_is_irrelevant() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+
+        for _ in 0..64 {
+            let mut entries = (0..(rng.next() % 32))
+                .map(|_| (format!("{:x}", rng.next() % 4096), rng.next()))
+                .collect::<Vec<_>>();
+
+            let forward = entries.iter().cloned().collect::<HashMap<_, _>>();
+            entries.reverse();
+            for idx in (1..entries.len()).rev() {
+                entries.swap(idx, rng.next() as usize % (idx + 1));
+            }
+            let shuffled = entries.into_iter().collect::<HashMap<_, _>>();
+
+            assert_eq!(dump(forward), dump(shuffled));
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+        for _ in 0..256 {
+            let value = (
+                rng.next(),
+                -(rng.next() as i64 >> 1),
+                f64::from_bits(rng.next()),
+                (0..rng.next() % 16)
+                    .map(|_| (rng.next() as u16, format!("{}", rng.next())))
+                    .collect::<BTreeMap<_, _>>(),
+            );
+
+            if value.2.is_nan() {
+                continue;
+            }
+
+            let encoded = dump(value.clone());
+            let decoded: (u64, i64, f64, BTreeMap<u16, String>) =
+                rehydrate(encoded.clone()).unwrap();
+
+            assert_eq!(decoded.0, value.0);
+            assert_eq!(decoded.1, value.1);
+            assert_eq!(decoded.2.to_bits(), value.2.to_bits());
+            assert_eq!(decoded.3, value.3);
+            assert_eq!(dump(decoded), encoded);
+        }
+    }
+
+    #[test]
+    fn rejects_indefinite_length() {
+        let result = rehydrate::<Vec<u8>>(vec![0x9f, 0x01, 0x02, 0xff]);
+
+        assert!(matches!(result, Err(CanonicalCborError::NonCanonical)));
+    }
+
+    #[test]
+    fn rejects_non_shortest_integer() {
+        let result = rehydrate::<u64>(vec![0x18, 0x05]);
+
+        assert!(matches!(result, Err(CanonicalCborError::NonCanonical)));
+    }
+
+    #[test]
+    fn rejects_non_shortest_float() {
+        let result = rehydrate::<f64>(vec![0xfa, 0x3f, 0x80, 0x00, 0x00]);
+
+        assert!(matches!(result, Err(CanonicalCborError::NonCanonical)));
+        assert_eq!(rehydrate::<f64>(vec![0xf9, 0x3c, 0x00]).unwrap(), 1.0);
+    }
+
+    #[test]
+    fn rejects_unsorted_keys() {
+        let result =
+            rehydrate::<BTreeMap<String, u8>>(vec![0xa2, 0x61, 0x62, 0x01, 0x61, 0x61, 0x02]);
+
+        assert!(matches!(result, Err(CanonicalCborError::NonCanonical)));
+    }
+
+    #[test]
+    fn half_precision_subnormals() {
+        let smallest = 2f64.powi(-24);
+
+        assert_eq!(dump(smallest), vec![0xf9, 0x00, 0x01]);
+        assert_eq!(dump(smallest * 3.0), vec![0xf9, 0x00, 0x03]);
+        assert_eq!(dump(2f64.powi(-15)), vec![0xf9, 0x02, 0x00]);
+        assert_eq!(rehydrate::<f64>(vec![0xf9, 0x00, 0x01]).unwrap(), smallest);
+        assert_eq!(dump(smallest / 2.0)[0], 0xfa);
+    }
+
+    #[test]
+    fn infinities_nan_and_negative_zero() {
+        assert_eq!(dump(f64::INFINITY), vec![0xf9, 0x7c, 0x00]);
+        assert_eq!(dump(f64::NEG_INFINITY), vec![0xf9, 0xfc, 0x00]);
+        assert_eq!(dump(f64::NAN), vec![0xf9, 0x7e, 0x00]);
+        assert_eq!(dump(-0.0f64), vec![0xf9, 0x80, 0x00]);
+
+        assert!(rehydrate::<f64>(vec![0xf9, 0x7e, 0x00]).unwrap().is_nan());
+        assert!(rehydrate::<f64>(vec![0xf9, 0x80, 0x00])
+            .unwrap()
+            .is_sign_negative());
+        assert!(matches!(
+            rehydrate::<f64>(vec![0xfb, 0x7f, 0xf8, 0, 0, 0, 0, 0, 0]),
+            Err(CanonicalCborError::NonCanonical)
+        ));
+    }
+}
@@ -0,0 +1,425 @@
+use crate::{
+    keyed::{Key, KeyedFetch, Keys},
+    resource::{
+        hash::{Algorithm, Digest},
+        manager::ResourceManager,
+        ProviderErrors, ProviderFailure, ResourceError,
+    },
+};
+use core_error::Error;
+use futures::{
+    future::{Shared, WeakShared},
+    Future, FutureExt,
+};
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    convert::Infallible,
+    fmt::{self, Debug, Display, Formatter},
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+#[derive(Clone)]
+pub struct CoalescedError(Arc<Mutex<Box<dyn Error + Send>>>);
+
+impl CoalescedError {
+    fn new(error: Box<dyn Error + Send>) -> Self {
+        CoalescedError(Arc::new(Mutex::new(error)))
+    }
+
+    fn boxed(&self) -> Box<dyn Error + Send> {
+        Box::new(self.clone())
+    }
+}
+
+impl Debug for CoalescedError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&*self.0.lock().unwrap(), f)
+    }
+}
+
+impl Display for CoalescedError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&*self.0.lock().unwrap(), f)
+    }
+}
+
+impl Error for CoalescedError {}
+
+#[derive(Clone)]
+enum Failure {
+    Provider(CoalescedError),
+    UnknownAlgorithm,
+    Store(CoalescedError),
+    NoStore,
+    Providers(Vec<(usize, Option<String>, CoalescedError)>),
+}
+
+impl From<ResourceError<Infallible>> for Failure {
+    fn from(input: ResourceError<Infallible>) -> Self {
+        match input {
+            ResourceError::Provider(e) => Failure::Provider(CoalescedError::new(e)),
+            ResourceError::UnknownAlgorithm => Failure::UnknownAlgorithm,
+            ResourceError::Rehydration(e) => match e {},
+            ResourceError::Store(e) => Failure::Store(CoalescedError::new(e)),
+            ResourceError::NoStore => Failure::NoStore,
+            ResourceError::Providers(ProviderErrors(failures)) => Failure::Providers(
+                failures
+                    .into_iter()
+                    .map(|failure| {
+                        (
+                            failure.index,
+                            failure.name,
+                            CoalescedError::new(failure.error),
+                        )
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl Failure {
+    fn rebuild(&self) -> ResourceError<Infallible> {
+        match self {
+            Failure::Provider(e) => ResourceError::Provider(e.boxed()),
+            Failure::UnknownAlgorithm => ResourceError::UnknownAlgorithm,
+            Failure::Store(e) => ResourceError::Store(e.boxed()),
+            Failure::NoStore => ResourceError::NoStore,
+            Failure::Providers(failures) => ResourceError::Providers(ProviderErrors(
+                failures
+                    .iter()
+                    .map(|(index, name, error)| ProviderFailure {
+                        index: *index,
+                        name: name.clone(),
+                        error: error.boxed(),
+                    })
+                    .collect(),
+            )),
+        }
+    }
+}
+
+type InFlight = Pin<Box<dyn Future<Output = Result<Option<Vec<u8>>, Failure>> + Send>>;
+
+struct Waiter {
+    shared: Option<Shared<InFlight>>,
+    key: Key,
+    registry: Arc<Mutex<HashMap<Key, WeakShared<InFlight>>>>,
+}
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        self.shared.take();
+
+        let mut registry = self.registry.lock().unwrap();
+
+        if registry
+            .get(&self.key)
+            .map(|weak| weak.upgrade().is_none())
+            .unwrap_or(false)
+        {
+            registry.remove(&self.key);
+        }
+    }
+}
+
+pub struct CoalescingResourceManager<M: ResourceManager> {
+    manager: M,
+    keys: Keys,
+    in_flight: Arc<Mutex<HashMap<Key, WeakShared<InFlight>>>>,
+}
+
+impl<M: ResourceManager> CoalescingResourceManager<M> {
+    pub fn new(manager: M) -> Self {
+        CoalescingResourceManager {
+            manager,
+            keys: Keys::default(),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Fetches for algorithms that were never registered here are passed
+    /// through to the inner manager without being coalesced.
+    pub fn algorithm<A: Algorithm + Any>(mut self) -> Self
+    where
+        A::Hash: Digest + 'static,
+    {
+        self.keys.register::<A>();
+        self
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.lock().unwrap().len()
+    }
+
+    fn coalesce(
+        &self,
+        algo: TypeId,
+        key: Key,
+        hash: Box<dyn FnMut() -> Box<dyn Any + Send> + Send>,
+    ) -> KeyedFetch
+    where
+        M::Fetch: Send + 'static,
+    {
+        let shared = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+
+            match in_flight.get(&key).and_then(WeakShared::upgrade) {
+                Some(shared) => shared,
+                None => {
+                    let fetch = self.manager.fetch(algo, hash);
+                    let registry = self.in_flight.clone();
+                    let entry = key.clone();
+
+                    let future: InFlight = Box::pin(async move {
+                        let result = fetch.await;
+                        registry.lock().unwrap().remove(&entry);
+                        result.map_err(Failure::from)
+                    });
+                    let shared: Shared<InFlight> = future.shared();
+
+                    if let Some(weak) = shared.downgrade() {
+                        in_flight.insert(key.clone(), weak);
+                    }
+
+                    shared
+                }
+            }
+        };
+
+        let mut waiter = Waiter {
+            shared: Some(shared),
+            key,
+            registry: self.in_flight.clone(),
+        };
+
+        Box::pin(async move {
+            let shared = waiter.shared.as_mut().unwrap();
+
+            shared.await.map_err(|failure| failure.rebuild())
+        })
+    }
+}
+
+impl<M: ResourceManager> ResourceManager for CoalescingResourceManager<M>
+where
+    M::Fetch: Send + 'static,
+{
+    type Fetch = KeyedFetch;
+
+    fn fetch(
+        &self,
+        algo: TypeId,
+        hash: Box<dyn FnMut() -> Box<dyn Any + Send> + Send>,
+    ) -> Self::Fetch {
+        self.keys.fetch(&self.manager, algo, hash, |key, hash| {
+            self.coalesce(algo, key, hash)
+        })
+    }
+
+    fn publish(
+        &self,
+        algo: TypeId,
+        hash: Box<dyn FnMut() -> Box<dyn Any + Send> + Send>,
+        data: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), ResourceError<Infallible>>> + Send>> {
+        self.manager.publish(algo, hash, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_util::TestError, Sha256, Sha256Sum};
+    use futures::{channel::oneshot, executor::block_on, future::join_all};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    type Outcome = fn() -> Result<Option<Vec<u8>>, ResourceError<Infallible>>;
+
+    struct Gated {
+        calls: Arc<AtomicUsize>,
+        gate: Shared<oneshot::Receiver<()>>,
+        outcome: Outcome,
+    }
+
+    impl ResourceManager for Gated {
+        type Fetch = Pin<
+            Box<dyn Future<Output = Result<Option<Vec<u8>>, ResourceError<Infallible>>> + Send>,
+        >;
+
+        fn fetch(
+            &self,
+            _: TypeId,
+            _: Box<dyn FnMut() -> Box<dyn Any + Send> + Send>,
+        ) -> Self::Fetch {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let (gate, outcome) = (self.gate.clone(), self.outcome);
+
+            Box::pin(async move {
+                let _ = gate.await;
+                outcome()
+            })
+        }
+    }
+
+    fn gated(
+        outcome: Outcome,
+    ) -> (
+        CoalescingResourceManager<Gated>,
+        Arc<AtomicUsize>,
+        oneshot::Sender<()>,
+    ) {
+        let (sender, receiver) = oneshot::channel();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let manager = CoalescingResourceManager::new(Gated {
+            calls: calls.clone(),
+            gate: receiver.shared(),
+            outcome,
+        });
+
+        (manager, calls, sender)
+    }
+
+    fn manager() -> (
+        CoalescingResourceManager<Gated>,
+        Arc<AtomicUsize>,
+        oneshot::Sender<()>,
+    ) {
+        let (manager, calls, sender) = gated(|| Ok(Some(b"data".to_vec())));
+
+        (manager.algorithm::<Sha256>(), calls, sender)
+    }
+
+    fn fetch(
+        manager: &CoalescingResourceManager<Gated>,
+    ) -> <CoalescingResourceManager<Gated> as ResourceManager>::Fetch {
+        ResourceManager::fetch(
+            manager,
+            TypeId::of::<Sha256>(),
+            Box::new(|| Box::new(Sha256Sum([1; 32])) as Box<dyn Any + Send>),
+        )
+    }
+
+    #[test]
+    fn concurrent_fetches_share_one_request() {
+        let (manager, calls, sender) = manager();
+
+        let fetches = (0..3).map(|_| fetch(&manager)).collect::<Vec<_>>();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(manager.in_flight(), 1);
+
+        sender.send(()).unwrap();
+
+        for result in block_on(join_all(fetches)) {
+            assert_eq!(result.unwrap(), Some(b"data".to_vec()));
+        }
+        assert_eq!(manager.in_flight(), 0);
+    }
+
+    #[test]
+    fn cancelled_waiter_leaves_others_running() {
+        let (manager, calls, sender) = manager();
+
+        let mut first = fetch(&manager);
+        let second = fetch(&manager);
+
+        assert!((&mut first).now_or_never().is_none());
+        drop(first);
+
+        assert_eq!(manager.in_flight(), 1);
+
+        sender.send(()).unwrap();
+
+        assert_eq!(block_on(second).unwrap(), Some(b"data".to_vec()));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(manager.in_flight(), 0);
+    }
+
+    #[test]
+    fn cancelling_every_waiter_clears_registry() {
+        let (manager, calls, _sender) = manager();
+
+        let (first, second) = (fetch(&manager), fetch(&manager));
+        drop(first);
+        drop(second);
+
+        assert_eq!(manager.in_flight(), 0);
+
+        let _third = fetch(&manager);
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(manager.in_flight(), 1);
+    }
+
+    #[test]
+    fn unregistered_algorithms_pass_through() {
+        let (manager, calls, sender) = gated(|| Ok(Some(b"data".to_vec())));
+
+        let fetches = (0..2).map(|_| fetch(&manager)).collect::<Vec<_>>();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(manager.in_flight(), 0);
+
+        sender.send(()).unwrap();
+
+        for result in block_on(join_all(fetches)) {
+            assert_eq!(result.unwrap(), Some(b"data".to_vec()));
+        }
+    }
+
+    #[test]
+    fn waiters_see_the_original_error_variant() {
+        let outcomes: [Outcome; 3] = [
+            || Err(ResourceError::NoStore),
+            || Err(ResourceError::Store(Box::new(TestError))),
+            || {
+                Err(ResourceError::Providers(ProviderErrors(vec![
+                    ProviderFailure {
+                        index: 1,
+                        name: Some("remote".to_owned()),
+                        error: Box::new(TestError),
+                    },
+                ])))
+            },
+        ];
+
+        for outcome in outcomes.iter() {
+            let (manager, calls, sender) = gated(*outcome);
+            let manager = manager.algorithm::<Sha256>();
+
+            let fetches = (0..2).map(|_| fetch(&manager)).collect::<Vec<_>>();
+            sender.send(()).unwrap();
+
+            for result in block_on(join_all(fetches)) {
+                match (result, outcome()) {
+                    (Err(ResourceError::NoStore), Err(ResourceError::NoStore)) => {}
+                    (Err(ResourceError::Store(_)), Err(ResourceError::Store(_))) => {}
+                    (Err(ResourceError::Providers(errors)), Err(ResourceError::Providers(_))) => {
+                        assert_eq!(errors.0.len(), 1);
+                        assert_eq!(errors.0[0].index, 1);
+                        assert_eq!(errors.0[0].name.as_deref(), Some("remote"));
+                    }
+                    _ => panic!("error variant was not preserved"),
+                }
+            }
+            assert_eq!(calls.load(Ordering::SeqCst), 1);
+        }
+    }
+
+    #[test]
+    fn publish_defaults_to_no_store() {
+        let (manager, _, _sender) = manager();
+
+        let result = block_on(ResourceManager::publish(
+            &manager,
+            TypeId::of::<Sha256>(),
+            Box::new(|| Box::new(Sha256Sum([1; 32])) as Box<dyn Any + Send>),
+            b"data".to_vec(),
+        ));
+
+        assert!(matches!(result, Err(ResourceError::NoStore)));
+    }
+}
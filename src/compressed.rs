@@ -0,0 +1,258 @@
+use crate::resource::Rehydrate;
+use core::marker::PhantomData;
+use core_error::Error;
+use futures::{
+    future::{ready, AndThen, MapErr, Ready},
+    TryFutureExt,
+};
+use std::io::{self, Read};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[bounds(where T: Error + 'static)]
+pub enum CompressedError<T> {
+    #[error("missing codec header")]
+    NoHeader,
+    #[error("unknown codec {0}")]
+    UnknownCodec(u8),
+    #[error("decompressed size exceeds limit")]
+    Limit,
+    #[error("codec error: {0}")]
+    Io(#[source] io::Error),
+    #[error("inner error: {0}")]
+    Inner(#[source] T),
+}
+
+impl<T> From<io::Error> for CompressedError<T> {
+    fn from(input: io::Error) -> Self {
+        CompressedError::Io(input)
+    }
+}
+
+pub trait Codec {
+    const ID: u8;
+
+    fn compress(data: &[u8]) -> io::Result<Vec<u8>>;
+    fn decoder<'a>(data: &'a [u8]) -> io::Result<Box<dyn Read + 'a>>;
+}
+
+#[cfg(feature = "zstd")]
+pub struct Zstd;
+
+#[cfg(feature = "zstd")]
+impl Codec for Zstd {
+    const ID: u8 = 1;
+
+    fn compress(data: &[u8]) -> io::Result<Vec<u8>> {
+        zstd::stream::encode_all(data, 0)
+    }
+
+    fn decoder<'a>(data: &'a [u8]) -> io::Result<Box<dyn Read + 'a>> {
+        Ok(Box::new(zstd::stream::read::Decoder::new(data)?))
+    }
+}
+
+#[cfg(feature = "deflate")]
+pub struct Deflate;
+
+#[cfg(feature = "deflate")]
+impl Codec for Deflate {
+    const ID: u8 = 2;
+
+    fn compress(data: &[u8]) -> io::Result<Vec<u8>> {
+        use flate2::{write::DeflateEncoder, Compression};
+        use std::io::Write;
+
+        let mut encoder = DeflateEncoder::new(vec![], Compression::default());
+        encoder.write_all(data)?;
+        encoder.finish()
+    }
+
+    fn decoder<'a>(data: &'a [u8]) -> io::Result<Box<dyn Read + 'a>> {
+        Ok(Box::new(flate2::read::DeflateDecoder::new(data)))
+    }
+}
+
+pub trait Limit {
+    const LIMIT: u64;
+}
+
+pub struct DefaultLimit;
+
+impl Limit for DefaultLimit {
+    const LIMIT: u64 = 256 * 1024 * 1024;
+}
+
+fn decoder<'a, C: Codec, T>(
+    id: u8,
+    data: &'a [u8],
+) -> Result<Box<dyn Read + 'a>, CompressedError<T>> {
+    Ok(match id {
+        id if id == C::ID => C::decoder(data)?,
+        #[cfg(feature = "zstd")]
+        Zstd::ID => Zstd::decoder(data)?,
+        #[cfg(feature = "deflate")]
+        Deflate::ID => Deflate::decoder(data)?,
+        id => return Err(CompressedError::UnknownCodec(id)),
+    })
+}
+
+fn read_limited<C: Codec, L: Limit, T>(data: &[u8]) -> Result<Vec<u8>, CompressedError<T>> {
+    let (id, data) = data.split_first().ok_or(CompressedError::NoHeader)?;
+
+    let mut buffer = vec![];
+    decoder::<C, T>(*id, data)?
+        .take(L::LIMIT + 1)
+        .read_to_end(&mut buffer)?;
+
+    if buffer.len() as u64 > L::LIMIT {
+        return Err(CompressedError::Limit);
+    }
+
+    Ok(buffer)
+}
+
+fn decompress<C: Codec, L: Limit, T>(data: Vec<u8>) -> Ready<Result<Vec<u8>, CompressedError<T>>> {
+    ready(read_limited::<C, L, T>(&data))
+}
+
+fn compress<C: Codec, T>(data: Vec<u8>) -> Ready<Result<Vec<u8>, CompressedError<T>>> {
+    ready(C::compress(&data).map_err(CompressedError::Io).map(|data| {
+        let mut buffer = Vec::with_capacity(data.len() + 1);
+        buffer.push(C::ID);
+        buffer.extend(data);
+        buffer
+    }))
+}
+
+pub struct Compressed<U, C, L = DefaultLimit>(PhantomData<(U, C, L)>);
+
+impl<T, U: Rehydrate<T>, C: Codec, L: Limit> Rehydrate<T> for Compressed<U, C, L> {
+    type RehydrateError = CompressedError<U::RehydrateError>;
+    type Rehydrate = AndThen<
+        Ready<Result<Vec<u8>, Self::RehydrateError>>,
+        MapErr<U::Rehydrate, fn(U::RehydrateError) -> Self::RehydrateError>,
+        fn(Vec<u8>) -> MapErr<U::Rehydrate, fn(U::RehydrateError) -> Self::RehydrateError>,
+    >;
+    type DumpError = CompressedError<U::DumpError>;
+    type Dump = AndThen<
+        MapErr<U::Dump, fn(U::DumpError) -> Self::DumpError>,
+        Ready<Result<Vec<u8>, Self::DumpError>>,
+        fn(Vec<u8>) -> Ready<Result<Vec<u8>, Self::DumpError>>,
+    >;
+
+    fn rehydrate(data: Vec<u8>) -> Self::Rehydrate {
+        decompress::<C, L, U::RehydrateError>(data).and_then(
+            (|data| {
+                U::rehydrate(data).map_err(
+                    CompressedError::Inner
+                        as fn(U::RehydrateError) -> CompressedError<U::RehydrateError>,
+                )
+            })
+                as fn(
+                    Vec<u8>,
+                ) -> MapErr<
+                    U::Rehydrate,
+                    fn(U::RehydrateError) -> CompressedError<U::RehydrateError>,
+                >,
+        )
+    }
+    fn dump(data: T) -> Self::Dump {
+        U::dump(data)
+            .map_err(CompressedError::Inner as fn(U::DumpError) -> CompressedError<U::DumpError>)
+            .and_then(
+                compress::<C, U::DumpError>
+                    as fn(Vec<u8>) -> Ready<Result<Vec<u8>, CompressedError<U::DumpError>>>,
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Convert;
+    use futures::executor::block_on;
+
+    struct Identity;
+
+    impl Codec for Identity {
+        const ID: u8 = 0x7f;
+
+        fn compress(data: &[u8]) -> io::Result<Vec<u8>> {
+            Ok(data.to_vec())
+        }
+
+        fn decoder<'a>(data: &'a [u8]) -> io::Result<Box<dyn Read + 'a>> {
+            Ok(Box::new(data))
+        }
+    }
+
+    struct Tiny;
+
+    impl Limit for Tiny {
+        const LIMIT: u64 = 4;
+    }
+
+    fn round_trip<C: Codec>() {
+        let data = b"compressible compressible compressible".to_vec();
+
+        let dumped = block_on(<Compressed<Convert, C> as Rehydrate<Vec<u8>>>::dump(
+            data.clone(),
+        ))
+        .unwrap();
+
+        assert_eq!(dumped[0], C::ID);
+
+        let rehydrated = block_on(<Compressed<Convert, C> as Rehydrate<Vec<u8>>>::rehydrate(
+            dumped,
+        ))
+        .unwrap();
+
+        assert_eq!(rehydrated, data);
+    }
+
+    #[test]
+    fn custom_codec_round_trip() {
+        round_trip::<Identity>();
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_round_trip() {
+        round_trip::<Zstd>();
+    }
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn deflate_round_trip() {
+        round_trip::<Deflate>();
+    }
+
+    #[test]
+    fn decompressed_size_is_limited() {
+        let dumped = block_on(<Compressed<Convert, Identity, Tiny> as Rehydrate<
+            Vec<u8>,
+        >>::dump(vec![0; 8]))
+        .unwrap();
+
+        let result = block_on(<Compressed<Convert, Identity, Tiny> as Rehydrate<
+            Vec<u8>,
+        >>::rehydrate(dumped));
+
+        assert!(matches!(result, Err(CompressedError::Limit)));
+    }
+
+    #[test]
+    fn unknown_header_is_rejected() {
+        let result = block_on(
+            <Compressed<Convert, Identity> as Rehydrate<Vec<u8>>>::rehydrate(vec![0xee, 1, 2, 3]),
+        );
+
+        assert!(matches!(result, Err(CompressedError::UnknownCodec(0xee))));
+
+        let result =
+            block_on(<Compressed<Convert, Identity> as Rehydrate<Vec<u8>>>::rehydrate(vec![]));
+
+        assert!(matches!(result, Err(CompressedError::NoHeader)));
+    }
+}
@@ -0,0 +1,306 @@
+use crate::{acquire, resource::Rehydrate, CoreError};
+use core::marker::PhantomData;
+use core_error::Error;
+use futures::Future;
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, CHACHA20_POLY1305, NONCE_LEN},
+    hmac::{sign, Key as HmacKey, HMAC_SHA256},
+};
+use std::{collections::HashMap, pin::Pin, sync::Arc};
+use thiserror::Error;
+
+const HEADER_LEN: usize = 5;
+const NONCE_CONTEXT: &[u8] = b"vessels-encrypted-nonce";
+
+#[derive(Debug, Error)]
+#[bounds(where T: Error + 'static)]
+pub enum EncryptedError<T> {
+    #[error("no active keyring")]
+    NoKeyring,
+    #[error("no current key")]
+    NoCurrentKey,
+    #[error("unknown key {0}")]
+    UnknownKey(u32),
+    #[error("unknown cipher {0}")]
+    UnknownCipher(u8),
+    #[error("malformed header")]
+    Header,
+    #[error("encryption failed")]
+    Encryption,
+    #[error("decryption failed")]
+    Decryption,
+    #[error("core error: {0}")]
+    Core(#[source] CoreError),
+    #[error("inner error: {0}")]
+    Inner(#[source] T),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Cipher {
+    Aes256Gcm,
+    ChaCha20Poly1305,
+}
+
+impl Cipher {
+    fn id(self) -> u8 {
+        match self {
+            Cipher::Aes256Gcm => 1,
+            Cipher::ChaCha20Poly1305 => 2,
+        }
+    }
+
+    fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(Cipher::Aes256Gcm),
+            2 => Some(Cipher::ChaCha20Poly1305),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Key {
+    cipher: Cipher,
+    data: [u8; 32],
+}
+
+impl Key {
+    pub fn aes_256_gcm(data: [u8; 32]) -> Self {
+        Key {
+            cipher: Cipher::Aes256Gcm,
+            data,
+        }
+    }
+
+    pub fn chacha20_poly1305(data: [u8; 32]) -> Self {
+        Key {
+            cipher: Cipher::ChaCha20Poly1305,
+            data,
+        }
+    }
+
+    fn sealing_key<T>(&self) -> Result<LessSafeKey, EncryptedError<T>> {
+        let algorithm = match self.cipher {
+            Cipher::Aes256Gcm => &AES_256_GCM,
+            Cipher::ChaCha20Poly1305 => &CHACHA20_POLY1305,
+        };
+
+        Ok(LessSafeKey::new(
+            UnboundKey::new(algorithm, &self.data).map_err(|_| EncryptedError::Encryption)?,
+        ))
+    }
+
+    fn nonce(&self, header: &[u8], data: &[u8]) -> [u8; NONCE_LEN] {
+        let subkey = sign(&HmacKey::new(HMAC_SHA256, &self.data), NONCE_CONTEXT);
+        let subkey = HmacKey::new(HMAC_SHA256, subkey.as_ref());
+
+        let mut input = Vec::with_capacity(header.len() + data.len());
+        input.extend_from_slice(header);
+        input.extend_from_slice(data);
+
+        let mut nonce = [0u8; NONCE_LEN];
+        nonce.copy_from_slice(&sign(&subkey, &input).as_ref()[..NONCE_LEN]);
+        nonce
+    }
+}
+
+#[derive(Clone)]
+pub struct Keyring {
+    keys: Arc<HashMap<u32, Key>>,
+    current: Option<u32>,
+}
+
+impl Keyring {
+    pub fn new() -> Self {
+        Keyring {
+            keys: Arc::new(HashMap::new()),
+            current: None,
+        }
+    }
+
+    pub fn insert(&mut self, id: u32, key: Key) {
+        Arc::make_mut(&mut self.keys).insert(id, key);
+    }
+
+    pub fn set_current(&mut self, id: u32) {
+        self.current = Some(id);
+    }
+
+    pub fn current(&self) -> Option<(u32, &Key)> {
+        self.current
+            .and_then(|id| self.keys.get(&id).map(|key| (id, key)))
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Key> {
+        self.keys.get(&id)
+    }
+}
+
+fn seal<T>(keyring: &Keyring, data: Vec<u8>) -> Result<Vec<u8>, EncryptedError<T>> {
+    let (id, key) = keyring.current().ok_or(EncryptedError::NoCurrentKey)?;
+
+    let mut header = Vec::with_capacity(HEADER_LEN + NONCE_LEN);
+    header.extend_from_slice(&id.to_be_bytes());
+    header.push(key.cipher.id());
+
+    // the nonce is derived from the plaintext so equal values seal to equal
+    // bytes and keep a stable content address
+    let nonce = key.nonce(&header, &data);
+
+    let mut data = data;
+    key.sealing_key::<T>()?
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(&header[..]),
+            &mut data,
+        )
+        .map_err(|_| EncryptedError::Encryption)?;
+
+    header.extend_from_slice(&nonce);
+    header.extend(data);
+
+    Ok(header)
+}
+
+fn open<T>(keyring: &Keyring, mut data: Vec<u8>) -> Result<Vec<u8>, EncryptedError<T>> {
+    if data.len() < HEADER_LEN + NONCE_LEN {
+        return Err(EncryptedError::Header);
+    }
+
+    let mut id = [0u8; 4];
+    id.copy_from_slice(&data[..4]);
+    let id = u32::from_be_bytes(id);
+
+    let cipher = Cipher::from_id(data[4]).ok_or(EncryptedError::UnknownCipher(data[4]))?;
+    let key = keyring.get(id).ok_or(EncryptedError::UnknownKey(id))?;
+
+    if key.cipher != cipher {
+        return Err(EncryptedError::Decryption);
+    }
+
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce.copy_from_slice(&data[HEADER_LEN..HEADER_LEN + NONCE_LEN]);
+
+    let mut sealed = data.split_off(HEADER_LEN + NONCE_LEN);
+
+    let len = key
+        .sealing_key::<T>()?
+        .open_in_place(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(&data[..HEADER_LEN]),
+            &mut sealed,
+        )
+        .map_err(|_| EncryptedError::Decryption)?
+        .len();
+
+    sealed.truncate(len);
+
+    Ok(sealed)
+}
+
+pub struct Encrypted<U>(PhantomData<U>);
+
+impl<T, U> Rehydrate<T> for Encrypted<U>
+where
+    T: Send + 'static,
+    U: Rehydrate<T>,
+    U::Rehydrate: Send + 'static,
+    U::RehydrateError: Send + 'static,
+    U::Dump: Send + 'static,
+    U::DumpError: Send + 'static,
+{
+    type RehydrateError = EncryptedError<U::RehydrateError>;
+    type Rehydrate = Pin<Box<dyn Future<Output = Result<T, Self::RehydrateError>> + Send>>;
+    type DumpError = EncryptedError<U::DumpError>;
+    type Dump = Pin<Box<dyn Future<Output = Result<Vec<u8>, Self::DumpError>> + Send>>;
+
+    fn rehydrate(data: Vec<u8>) -> Self::Rehydrate {
+        let keyring = acquire::<Keyring>();
+
+        Box::pin(async move {
+            let keyring = keyring
+                .await
+                .map_err(EncryptedError::Core)?
+                .ok_or(EncryptedError::NoKeyring)?;
+
+            let data = open(&keyring, data)?;
+
+            U::rehydrate(data).await.map_err(EncryptedError::Inner)
+        })
+    }
+
+    fn dump(data: T) -> Self::Dump {
+        let keyring = acquire::<Keyring>();
+
+        Box::pin(async move {
+            let data = U::dump(data).await.map_err(EncryptedError::Inner)?;
+
+            let keyring = keyring
+                .await
+                .map_err(EncryptedError::Core)?
+                .ok_or(EncryptedError::NoKeyring)?;
+
+            seal(&keyring, data)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+
+    fn keyring() -> Keyring {
+        let mut keyring = Keyring::new();
+        keyring.insert(1, Key::aes_256_gcm([7u8; 32]));
+        keyring.insert(2, Key::chacha20_poly1305([9u8; 32]));
+        keyring.set_current(1);
+        keyring
+    }
+
+    #[test]
+    fn sealing_is_deterministic() {
+        let keyring = keyring();
+
+        let a = seal::<Infallible>(&keyring, b"value".to_vec()).unwrap();
+        let b = seal::<Infallible>(&keyring, b"value".to_vec()).unwrap();
+        let c = seal::<Infallible>(&keyring, b"other".to_vec()).unwrap();
+
+        assert_eq!(a, b);
+        assert!(a != c);
+        assert!(a[HEADER_LEN..HEADER_LEN + NONCE_LEN] != c[HEADER_LEN..HEADER_LEN + NONCE_LEN]);
+    }
+
+    #[test]
+    fn round_trip_across_rotation() {
+        let mut keyring = keyring();
+
+        let old = seal::<Infallible>(&keyring, b"value".to_vec()).unwrap();
+        keyring.set_current(2);
+        let new = seal::<Infallible>(&keyring, b"value".to_vec()).unwrap();
+
+        assert!(old != new);
+        assert_eq!(
+            open::<Infallible>(&keyring, old).unwrap(),
+            b"value".to_vec()
+        );
+        assert_eq!(
+            open::<Infallible>(&keyring, new).unwrap(),
+            b"value".to_vec()
+        );
+    }
+
+    #[test]
+    fn tampering_is_detected() {
+        let keyring = keyring();
+
+        let mut sealed = seal::<Infallible>(&keyring, b"value".to_vec()).unwrap();
+        let last = sealed.len() - 1;
+        sealed[last] ^= 1;
+
+        assert!(matches!(
+            open::<Infallible>(&keyring, sealed),
+            Err(EncryptedError::Decryption)
+        ));
+    }
+}
@@ -0,0 +1,164 @@
+use crate::resource::{domain::Domain, Rehydrate};
+use futures::future::{ready, Ready};
+use serde::{de::DeserializeOwned, Serialize};
+
+#[cfg(feature = "json")]
+pub struct Json;
+
+#[cfg(feature = "json")]
+impl<T: DeserializeOwned + Serialize> Rehydrate<T> for Json {
+    type RehydrateError = serde_json::Error;
+    type Rehydrate = Ready<Result<T, Self::RehydrateError>>;
+    type DumpError = serde_json::Error;
+    type Dump = Ready<Result<Vec<u8>, Self::DumpError>>;
+
+    fn rehydrate(data: Vec<u8>) -> Self::Rehydrate {
+        ready(serde_json::from_slice(&data))
+    }
+    fn dump(data: T) -> Self::Dump {
+        ready(serde_json::to_vec(&data))
+    }
+}
+
+#[cfg(feature = "json")]
+impl Domain for Json {
+    const TAG: &'static str = "json";
+}
+
+#[cfg(feature = "msgpack")]
+pub struct MsgPack;
+
+#[cfg(feature = "msgpack")]
+impl<T: DeserializeOwned + Serialize> Rehydrate<T> for MsgPack {
+    type RehydrateError = rmp_serde::decode::Error;
+    type Rehydrate = Ready<Result<T, Self::RehydrateError>>;
+    type DumpError = rmp_serde::encode::Error;
+    type Dump = Ready<Result<Vec<u8>, Self::DumpError>>;
+
+    fn rehydrate(data: Vec<u8>) -> Self::Rehydrate {
+        ready(rmp_serde::from_slice(&data))
+    }
+    fn dump(data: T) -> Self::Dump {
+        ready(rmp_serde::to_vec_named(&data))
+    }
+}
+
+#[cfg(feature = "msgpack")]
+impl Domain for MsgPack {
+    const TAG: &'static str = "msgpack";
+}
+
+#[cfg(feature = "bincode")]
+pub struct Bincode;
+
+#[cfg(feature = "bincode")]
+impl<T: DeserializeOwned + Serialize> Rehydrate<T> for Bincode {
+    type RehydrateError = bincode::Error;
+    type Rehydrate = Ready<Result<T, Self::RehydrateError>>;
+    type DumpError = bincode::Error;
+    type Dump = Ready<Result<Vec<u8>, Self::DumpError>>;
+
+    fn rehydrate(data: Vec<u8>) -> Self::Rehydrate {
+        ready(bincode::deserialize(&data))
+    }
+    fn dump(data: T) -> Self::Dump {
+        ready(bincode::serialize(&data))
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl Domain for Bincode {
+    const TAG: &'static str = "bincode";
+}
+
+#[cfg(feature = "postcard")]
+pub struct Postcard;
+
+#[cfg(feature = "postcard")]
+impl<T: DeserializeOwned + Serialize> Rehydrate<T> for Postcard {
+    type RehydrateError = postcard::Error;
+    type Rehydrate = Ready<Result<T, Self::RehydrateError>>;
+    type DumpError = postcard::Error;
+    type Dump = Ready<Result<Vec<u8>, Self::DumpError>>;
+
+    fn rehydrate(data: Vec<u8>) -> Self::Rehydrate {
+        ready(postcard::from_bytes(&data))
+    }
+    fn dump(data: T) -> Self::Dump {
+        ready(postcard::to_allocvec(&data))
+    }
+}
+
+#[cfg(feature = "postcard")]
+impl Domain for Postcard {
+    const TAG: &'static str = "postcard";
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+    use crate::{
+        resource::{
+            manager::{ProviderMetadata, ResourceRegistrant},
+            store::ResourceStoreExt,
+            ResourceManagerExt,
+        },
+        test_util::TestHasher,
+        Cbor, MemoryStore, Resource, Sha256, SimpleResourceManager,
+    };
+    use futures::executor::block_on;
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    struct Value {
+        id: u32,
+        name: String,
+    }
+
+    #[test]
+    fn transcode_cbor_json_round_trip() {
+        let store = MemoryStore::<Sha256>::new();
+        let value = Value {
+            id: 7,
+            name: "seven".to_owned(),
+        };
+
+        let mut manager = SimpleResourceManager::new();
+        block_on(ResourceRegistrant::<Sha256, _>::register_provider(
+            &mut manager,
+            store.clone(),
+            ProviderMetadata::default(),
+        ))
+        .unwrap();
+
+        let cbor: Resource<Value, Cbor, Sha256> = block_on(ResourceStoreExt::intern::<
+            TestHasher,
+            _,
+            _,
+        >(&store, value.clone()))
+        .unwrap();
+
+        let json: Resource<Value, Json, Sha256> =
+            block_on(store.transcode::<TestHasher, _, _, _, _>(&manager, cbor.clone()))
+                .unwrap()
+                .unwrap();
+        let expected = block_on(Resource::<Value, Json, Sha256>::compute::<TestHasher>(
+            value.clone(),
+        ))
+        .unwrap();
+
+        assert!(json.hash() == expected.hash());
+        assert!(json.hash() != cbor.hash());
+        assert_eq!(
+            block_on(ResourceManagerExt::fetch(&manager, json.clone())).unwrap(),
+            Some(value)
+        );
+
+        let back: Resource<Value, Cbor, Sha256> =
+            block_on(store.transcode::<TestHasher, _, _, _, _>(&manager, json))
+                .unwrap()
+                .unwrap();
+
+        assert!(back.hash() == cbor.hash());
+    }
+}
@@ -0,0 +1,437 @@
+use crate::{
+    blocking::{default_offload, offload, Offload, OffloadReader},
+    resource::{
+        hash::{Algorithm, Digest, Hasher},
+        provider::ResourceProvider,
+        store::ResourceStore,
+        stream::{
+            erase_reader, verify_reader, BufferReader, ErasedReader, StreamingResourceProvider,
+        },
+    },
+};
+use core::{
+    marker::PhantomData,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use futures::Future;
+use std::{
+    fs::{self, File},
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+    pin::Pin,
+    process,
+    sync::Arc,
+};
+use thiserror::Error;
+
+static TEMPORARY: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Error)]
+pub enum FsStoreError {
+    #[error("io error: {0}")]
+    Io(#[source] io::Error),
+    #[error("hash mismatch for stored resource")]
+    Mismatch,
+}
+
+impl From<io::Error> for FsStoreError {
+    fn from(input: io::Error) -> Self {
+        FsStoreError::Io(input)
+    }
+}
+
+pub type HashVerifier<A> = Arc<dyn Fn(&<A as Algorithm>::Hash, &[u8]) -> bool + Send + Sync>;
+
+fn verify<A: Algorithm, H: Hasher<A>>(hash: &A::Hash, data: &[u8]) -> bool
+where
+    A::Hash: PartialEq,
+{
+    let mut hasher = H::new();
+    hasher.write(data);
+    &hasher.hash() == hash
+}
+
+enum Verification<A: Algorithm> {
+    Hasher,
+    Custom(HashVerifier<A>),
+    Disabled,
+}
+
+impl<A: Algorithm> Clone for Verification<A> {
+    fn clone(&self) -> Self {
+        match self {
+            Verification::Hasher => Verification::Hasher,
+            Verification::Custom(verifier) => Verification::Custom(verifier.clone()),
+            Verification::Disabled => Verification::Disabled,
+        }
+    }
+}
+
+pub struct FsStore<A: Algorithm, H: Hasher<A>> {
+    root: Arc<PathBuf>,
+    verification: Verification<A>,
+    offload: Offload,
+    ty: PhantomData<fn() -> H>,
+}
+
+impl<A: Algorithm, H: Hasher<A>> Clone for FsStore<A, H> {
+    fn clone(&self) -> Self {
+        FsStore {
+            root: self.root.clone(),
+            verification: self.verification.clone(),
+            offload: self.offload.clone(),
+            ty: PhantomData,
+        }
+    }
+}
+
+impl<A: Algorithm, H: Hasher<A> + 'static> FsStore<A, H>
+where
+    A::Hash: Digest + PartialEq,
+{
+    pub fn new<P: Into<PathBuf>>(root: P) -> Result<Self, FsStoreError> {
+        let root = root.into();
+
+        fs::create_dir_all(root.join("tmp"))?;
+
+        Ok(FsStore {
+            root: Arc::new(root),
+            verification: Verification::Hasher,
+            offload: default_offload(),
+            ty: PhantomData,
+        })
+    }
+
+    pub fn with_verifier(mut self, verifier: HashVerifier<A>) -> Self {
+        self.verification = Verification::Custom(verifier);
+        self
+    }
+
+    pub fn without_verification(mut self) -> Self {
+        self.verification = Verification::Disabled;
+        self
+    }
+
+    pub fn with_offload(mut self, offload: Offload) -> Self {
+        self.offload = offload;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn path(&self, hash: &A::Hash) -> PathBuf {
+        let hex = hash.to_hex();
+        let (shard, name) = hex.split_at(2.min(hex.len()));
+
+        self.root.join(shard).join(name)
+    }
+
+    fn read(&self, hash: &A::Hash) -> Result<Option<Vec<u8>>, FsStoreError> {
+        let data = match fs::read(self.path(hash)) {
+            Ok(data) => data,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let valid = match &self.verification {
+            Verification::Hasher => verify::<A, H>(hash, &data),
+            Verification::Custom(verifier) => verifier(hash, &data),
+            Verification::Disabled => true,
+        };
+
+        if !valid {
+            return Err(FsStoreError::Mismatch);
+        }
+
+        Ok(Some(data))
+    }
+
+    fn open(&self, hash: &A::Hash) -> Result<Option<File>, FsStoreError> {
+        match File::open(self.path(hash)) {
+            Ok(file) => Ok(Some(file)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn run<T: Send + 'static, F: FnOnce(&Self) -> Result<T, FsStoreError> + Send + 'static>(
+        &self,
+        task: F,
+    ) -> Pin<Box<dyn Future<Output = Result<T, FsStoreError>> + Send>>
+    where
+        A: 'static,
+        A::Hash: Send + Sync,
+    {
+        let store = self.clone();
+
+        let task = offload(self.offload.clone(), move || task(&store));
+
+        Box::pin(async move { task.await.map_err(io::Error::from)? })
+    }
+
+    fn write(&self, hash: &A::Hash, data: &[u8]) -> Result<(), FsStoreError> {
+        let path = self.path(hash);
+
+        if path.exists() {
+            return Ok(());
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let temporary = self.root.join("tmp").join(format!(
+            "{}-{}",
+            process::id(),
+            TEMPORARY.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let result = (|| {
+            let mut file = File::create(&temporary)?;
+            file.write_all(data)?;
+            file.sync_all()?;
+            fs::rename(&temporary, &path)
+        })();
+
+        if result.is_err() {
+            let _ = fs::remove_file(&temporary);
+        }
+
+        Ok(result?)
+    }
+
+    fn delete(&self, hash: &A::Hash) -> Result<bool, FsStoreError> {
+        match fs::remove_file(self.path(hash)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn scan(&self) -> Result<Vec<A::Hash>, FsStoreError> {
+        let mut hashes = vec![];
+
+        for shard in fs::read_dir(&*self.root)? {
+            let shard = shard?;
+
+            if !shard.file_type()?.is_dir() {
+                continue;
+            }
+
+            let prefix = shard.file_name();
+            let prefix = match prefix.to_str() {
+                Some(prefix) if prefix.len() == 2 => prefix.to_owned(),
+                _ => continue,
+            };
+
+            for entry in fs::read_dir(shard.path())? {
+                let entry = entry?;
+
+                if let Some(hash) = entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| A::Hash::from_hex(&format!("{}{}", prefix, name)))
+                {
+                    hashes.push(hash);
+                }
+            }
+        }
+
+        Ok(hashes)
+    }
+}
+
+impl<A: Algorithm, H: Hasher<A> + 'static> ResourceProvider<A> for FsStore<A, H>
+where
+    A: 'static,
+    A::Hash: Digest + PartialEq + Send + Sync + 'static,
+{
+    type Fetch = Pin<Box<dyn Future<Output = Result<Option<Vec<u8>>, FsStoreError>> + Send>>;
+
+    fn fetch(&self, hash: A::Hash) -> Self::Fetch {
+        self.run(move |store| store.read(&hash))
+    }
+}
+
+impl<A: Algorithm, H: Hasher<A> + 'static> ResourceStore<A> for FsStore<A, H>
+where
+    A: 'static,
+    A::Hash: Digest + PartialEq + Send + Sync + 'static,
+{
+    type Put = Pin<Box<dyn Future<Output = Result<(), FsStoreError>> + Send>>;
+    type Remove = Pin<Box<dyn Future<Output = Result<bool, FsStoreError>> + Send>>;
+    type Contains = Pin<Box<dyn Future<Output = Result<bool, FsStoreError>> + Send>>;
+
+    fn put_raw(&self, hash: A::Hash, data: Vec<u8>) -> Self::Put {
+        self.run(move |store| store.write(&hash, &data))
+    }
+
+    fn remove(&self, hash: A::Hash) -> Self::Remove {
+        self.run(move |store| store.delete(&hash))
+    }
+
+    fn contains(&self, hash: A::Hash) -> Self::Contains {
+        self.run(move |store| Ok(store.path(&hash).is_file()))
+    }
+}
+
+impl<A: Algorithm, H: Hasher<A> + Send + 'static> StreamingResourceProvider<A> for FsStore<A, H>
+where
+    A: 'static,
+    A::Hash: Digest + PartialEq + Clone + Send + Sync + 'static,
+{
+    type Fetch = Pin<Box<dyn Future<Output = Result<Option<ErasedReader>, FsStoreError>> + Send>>;
+
+    fn fetch_stream(&self, hash: A::Hash) -> Self::Fetch {
+        if let Verification::Custom(_) = self.verification {
+            let read = self.run(move |store| store.read(&hash));
+
+            return Box::pin(async move {
+                Ok(read
+                    .await?
+                    .map(|data| erase_reader(BufferReader::new(data))))
+            });
+        }
+
+        let open = self.run({
+            let hash = hash.clone();
+            move |store| store.open(&hash)
+        });
+        let offload = self.offload.clone();
+        let verify = matches!(self.verification, Verification::Hasher);
+
+        Box::pin(async move {
+            Ok(open.await?.map(|file| {
+                let reader = erase_reader(OffloadReader::new(offload, file));
+
+                if verify {
+                    verify_reader::<A, H>(reader, hash)
+                } else {
+                    reader
+                }
+            }))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        resource::{domain::Separated, hash::HasherExt, stream::read_to_end},
+        test_util::{temp_dir, TestHasher},
+        Convert, Sha256, Sha256Sum,
+    };
+    use futures::executor::block_on;
+
+    type Store = FsStore<Sha256, TestHasher>;
+
+    #[test]
+    fn round_trip() {
+        let store = Store::new(temp_dir("fs-round-trip")).unwrap();
+        let hash = <TestHasher as HasherExt<Sha256>>::hash(b"data".to_vec());
+
+        block_on(store.put_raw(hash, b"data".to_vec())).unwrap();
+
+        assert!(block_on(store.contains(hash)).unwrap());
+        assert_eq!(block_on(store.fetch(hash)).unwrap(), Some(b"data".to_vec()));
+        assert_eq!(store.scan().unwrap().len(), 1);
+        assert!(block_on(store.remove(hash)).unwrap());
+        assert_eq!(block_on(store.fetch(hash)).unwrap(), None);
+    }
+
+    #[test]
+    fn detects_corruption() {
+        let store = Store::new(temp_dir("fs-corruption")).unwrap();
+        let hash = <TestHasher as HasherExt<Sha256>>::hash(b"data".to_vec());
+
+        block_on(store.put_raw(hash, b"data".to_vec())).unwrap();
+        fs::write(store.path(&hash), b"tampered").unwrap();
+
+        assert!(matches!(
+            block_on(store.fetch(hash)),
+            Err(FsStoreError::Mismatch)
+        ));
+        assert_eq!(
+            block_on(store.clone().without_verification().fetch(hash)).unwrap(),
+            Some(b"tampered".to_vec())
+        );
+    }
+
+    #[test]
+    fn separated_hashes_use_configured_verifier() {
+        type Domain = Separated<TestHasher, Vec<u8>, Convert>;
+
+        let store = Store::new(temp_dir("fs-separated")).unwrap();
+        let hash = <Domain as HasherExt<Sha256>>::hash(b"data".to_vec());
+
+        block_on(store.put_raw(hash, b"data".to_vec())).unwrap();
+
+        assert!(matches!(
+            block_on(store.fetch(hash)),
+            Err(FsStoreError::Mismatch)
+        ));
+
+        let store = store.with_verifier(Arc::new(|hash: &Sha256Sum, data: &[u8]| {
+            <Domain as HasherExt<Sha256>>::hash(data.to_vec()) == *hash
+        }));
+
+        assert_eq!(block_on(store.fetch(hash)).unwrap(), Some(b"data".to_vec()));
+    }
+
+    #[test]
+    fn io_runs_on_offload() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+
+        let store = Store::new(temp_dir("fs-offload"))
+            .unwrap()
+            .with_offload(Arc::new(move |task| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                task()
+            }));
+        let hash = <TestHasher as HasherExt<Sha256>>::hash(b"data".to_vec());
+
+        let put = store.put_raw(hash, b"data".to_vec());
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        block_on(put).unwrap();
+        block_on(store.fetch(hash)).unwrap();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn streams_and_verifies_incrementally() {
+        let store = Store::new(temp_dir("fs-stream")).unwrap();
+        let data = (0..100_000u32).map(|i| i as u8).collect::<Vec<_>>();
+        let hash = <TestHasher as HasherExt<Sha256>>::hash(data.clone());
+
+        block_on(store.put_raw(hash, data.clone())).unwrap();
+
+        let reader = block_on(store.fetch_stream(hash)).unwrap().unwrap();
+        assert_eq!(block_on(read_to_end(reader)).unwrap(), data);
+
+        let missing = <TestHasher as HasherExt<Sha256>>::hash(b"missing".to_vec());
+        assert!(block_on(store.fetch_stream(missing)).unwrap().is_none());
+    }
+
+    #[test]
+    fn streaming_detects_corruption() {
+        let store = Store::new(temp_dir("fs-stream-corruption")).unwrap();
+        let hash = <TestHasher as HasherExt<Sha256>>::hash(b"data".to_vec());
+
+        block_on(store.put_raw(hash, b"data".to_vec())).unwrap();
+        fs::write(store.path(&hash), b"tampered").unwrap();
+
+        let reader = block_on(store.fetch_stream(hash)).unwrap().unwrap();
+        assert!(block_on(read_to_end(reader)).is_err());
+
+        let reader = block_on(store.clone().without_verification().fetch_stream(hash))
+            .unwrap()
+            .unwrap();
+        assert_eq!(block_on(read_to_end(reader)).unwrap(), b"tampered".to_vec());
+    }
+}
@@ -0,0 +1,644 @@
+use crate::{
+    resource::{
+        hash::{Digest, Hasher},
+        provider::ResourceProvider,
+    },
+    Ring, Sha1, Sha1Sum,
+};
+use flate2::read::ZlibDecoder;
+use futures::future::{ready, Ready};
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, ErrorKind, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use thiserror::Error;
+
+const OBJ_BLOB: u8 = 3;
+const OBJ_OFS_DELTA: u8 = 6;
+const OBJ_REF_DELTA: u8 = 7;
+const MAX_DELTA_DEPTH: usize = 4096;
+const MAX_OBJECT_SIZE: usize = 1 << 30;
+const MAX_PREALLOCATION: usize = 1 << 20;
+const MAX_HEADER: usize = 32;
+
+#[derive(Debug, Error)]
+pub enum GitError {
+    #[error("io error: {0}")]
+    Io(#[source] io::Error),
+    #[error("corrupt object database: {0}")]
+    Corrupt(&'static str),
+}
+
+impl From<io::Error> for GitError {
+    fn from(input: io::Error) -> Self {
+        GitError::Io(input)
+    }
+}
+
+struct PackIndex {
+    pack: PathBuf,
+    index: Vec<u8>,
+    count: usize,
+}
+
+fn be_u32(data: &[u8], at: usize) -> Result<u32, GitError> {
+    let bytes = data
+        .get(at..at + 4)
+        .ok_or(GitError::Corrupt("truncated pack index"))?;
+    let mut buffer = [0u8; 4];
+    buffer.copy_from_slice(bytes);
+    Ok(u32::from_be_bytes(buffer))
+}
+
+impl PackIndex {
+    fn open(index: &Path) -> Result<Self, GitError> {
+        let data = fs::read(index)?;
+
+        if data.get(..4) != Some(&[0xff, b't', b'O', b'c'][..]) || be_u32(&data, 4)? != 2 {
+            return Err(GitError::Corrupt("unsupported pack index version"));
+        }
+
+        let count = be_u32(&data, 8 + 255 * 4)? as usize;
+
+        if data.len() < 8 + 256 * 4 + count * (20 + 4 + 4) + 40 {
+            return Err(GitError::Corrupt("truncated pack index"));
+        }
+
+        Ok(PackIndex {
+            pack: index.with_extension("pack"),
+            index: data,
+            count,
+        })
+    }
+
+    fn find(&self, hash: &[u8; 20]) -> Result<Option<u64>, GitError> {
+        let fanout = |byte: usize| be_u32(&self.index, 8 + byte * 4).map(|idx| idx as usize);
+
+        let mut low = if hash[0] == 0 {
+            0
+        } else {
+            fanout(hash[0] as usize - 1)?
+        };
+        let mut high = fanout(hash[0] as usize)?;
+
+        if low > high || high > self.count {
+            return Err(GitError::Corrupt("pack index fanout out of range"));
+        }
+
+        let names = 8 + 256 * 4;
+
+        while low < high {
+            let middle = (low + high) / 2;
+            let name = &self.index[names + middle * 20..names + middle * 20 + 20];
+
+            match name.cmp(&hash[..]) {
+                core::cmp::Ordering::Less => low = middle + 1,
+                core::cmp::Ordering::Greater => high = middle,
+                core::cmp::Ordering::Equal => return self.offset(middle).map(Some),
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn offset(&self, position: usize) -> Result<u64, GitError> {
+        let offsets = 8 + 256 * 4 + self.count * (20 + 4);
+        let offset = be_u32(&self.index, offsets + position * 4)?;
+
+        if offset & 0x8000_0000 == 0 {
+            return Ok(offset as u64);
+        }
+
+        let large = offsets + self.count * 4 + (offset & 0x7fff_ffff) as usize * 8;
+        let high = be_u32(&self.index, large)? as u64;
+        let low = be_u32(&self.index, large + 4)? as u64;
+
+        Ok((high << 32) | low)
+    }
+}
+
+fn read_byte<R: Read>(reader: &mut R) -> Result<u8, GitError> {
+    let mut byte = [0u8; 1];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn varint(value: usize, byte: u8, shift: u32) -> Result<usize, GitError> {
+    let part = (byte & 0x7f) as usize;
+
+    match part.checked_shl(shift) {
+        Some(shifted) if shifted >> shift == part => Ok(value | shifted),
+        _ => Err(GitError::Corrupt("varint overflow")),
+    }
+}
+
+fn allocate(size: usize) -> Result<Vec<u8>, GitError> {
+    if size > MAX_OBJECT_SIZE {
+        return Err(GitError::Corrupt("object too large"));
+    }
+
+    Ok(Vec::with_capacity(size.min(MAX_PREALLOCATION)))
+}
+
+fn inflate<R: Read>(reader: R, size: usize) -> Result<Vec<u8>, GitError> {
+    let mut data = allocate(size)?;
+    ZlibDecoder::new(reader)
+        .take(size as u64)
+        .read_to_end(&mut data)?;
+
+    if data.len() != size {
+        return Err(GitError::Corrupt("object size mismatch"));
+    }
+
+    Ok(data)
+}
+
+fn delta_size(delta: &[u8], position: &mut usize) -> Result<usize, GitError> {
+    let mut size = 0usize;
+    let mut shift = 0;
+
+    loop {
+        let byte = *delta
+            .get(*position)
+            .ok_or(GitError::Corrupt("truncated delta"))?;
+        *position += 1;
+        size = varint(size, byte, shift)?;
+        shift += 7;
+
+        if byte & 0x80 == 0 {
+            return Ok(size);
+        }
+    }
+}
+
+fn apply_delta(base: &[u8], delta: &[u8]) -> Result<Vec<u8>, GitError> {
+    let mut position = 0;
+
+    if delta_size(delta, &mut position)? != base.len() {
+        return Err(GitError::Corrupt("delta base size mismatch"));
+    }
+
+    let size = delta_size(delta, &mut position)?;
+    let mut data = allocate(size)?;
+
+    while position < delta.len() {
+        let op = delta[position];
+        position += 1;
+
+        if op & 0x80 != 0 {
+            let mut offset = 0usize;
+            let mut len = 0usize;
+
+            for bit in 0..4 {
+                if op & (1 << bit) != 0 {
+                    let byte = *delta
+                        .get(position)
+                        .ok_or(GitError::Corrupt("truncated delta"))?;
+                    offset |= (byte as usize) << (bit * 8);
+                    position += 1;
+                }
+            }
+
+            for bit in 0..3 {
+                if op & (0x10 << bit) != 0 {
+                    let byte = *delta
+                        .get(position)
+                        .ok_or(GitError::Corrupt("truncated delta"))?;
+                    len |= (byte as usize) << (bit * 8);
+                    position += 1;
+                }
+            }
+
+            if len == 0 {
+                len = 0x10000;
+            }
+
+            data.extend_from_slice(
+                base.get(offset..offset.saturating_add(len))
+                    .ok_or(GitError::Corrupt("delta copy out of range"))?,
+            );
+        } else if op != 0 {
+            let len = op as usize;
+            data.extend_from_slice(
+                delta
+                    .get(position..position.saturating_add(len))
+                    .ok_or(GitError::Corrupt("truncated delta"))?,
+            );
+            position += len;
+        } else {
+            return Err(GitError::Corrupt("reserved delta opcode"));
+        }
+    }
+
+    if data.len() != size {
+        return Err(GitError::Corrupt("delta result size mismatch"));
+    }
+
+    Ok(data)
+}
+
+#[derive(Clone)]
+pub struct GitObjectProvider {
+    objects: Arc<PathBuf>,
+    packs: Arc<Vec<PackIndex>>,
+}
+
+impl GitObjectProvider {
+    pub fn open<P: AsRef<Path>>(git_dir: P) -> Result<Self, GitError> {
+        let objects = git_dir.as_ref().join("objects");
+        let mut packs = vec![];
+
+        match fs::read_dir(objects.join("pack")) {
+            Ok(entries) => {
+                for entry in entries {
+                    let path = entry?.path();
+
+                    if path.extension().and_then(|ext| ext.to_str()) == Some("idx") {
+                        packs.push(PackIndex::open(&path)?);
+                    }
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        Ok(GitObjectProvider {
+            objects: Arc::new(objects),
+            packs: Arc::new(packs),
+        })
+    }
+
+    fn loose(&self, hash: &Sha1Sum) -> Result<Option<(u8, Vec<u8>)>, GitError> {
+        let hex = hash.to_hex();
+        let file = match File::open(self.objects.join(&hex[..2]).join(&hex[2..])) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut decoder = ZlibDecoder::new(file);
+        let mut header = vec![];
+
+        loop {
+            match read_byte(&mut decoder)? {
+                0 => break,
+                _ if header.len() >= MAX_HEADER => {
+                    return Err(GitError::Corrupt("missing loose object header"))
+                }
+                byte => header.push(byte),
+            }
+        }
+
+        let (kind, size) = {
+            let header = core::str::from_utf8(&header)
+                .map_err(|_| GitError::Corrupt("invalid loose object header"))?;
+            let mut parts = header.splitn(2, ' ');
+            let kind = match parts.next() {
+                Some("commit") => 1,
+                Some("tree") => 2,
+                Some("blob") => OBJ_BLOB,
+                Some("tag") => 4,
+                _ => return Err(GitError::Corrupt("unknown loose object type")),
+            };
+            let size: usize = parts
+                .next()
+                .and_then(|size| size.parse().ok())
+                .ok_or(GitError::Corrupt("invalid loose object size"))?;
+            (kind, size)
+        };
+
+        let mut content = allocate(size)?;
+        decoder.take(size as u64 + 1).read_to_end(&mut content)?;
+
+        if content.len() != size {
+            return Err(GitError::Corrupt("object size mismatch"));
+        }
+
+        Ok(Some((kind, content)))
+    }
+
+    fn packed(&self, hash: &Sha1Sum, depth: usize) -> Result<Option<(u8, Vec<u8>)>, GitError> {
+        for pack in self.packs.iter() {
+            if let Some(offset) = pack.find(&hash.0)? {
+                return self.unpack(pack, offset, depth).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn unpack(
+        &self,
+        pack: &PackIndex,
+        offset: u64,
+        depth: usize,
+    ) -> Result<(u8, Vec<u8>), GitError> {
+        if depth > MAX_DELTA_DEPTH {
+            return Err(GitError::Corrupt("delta chain too deep"));
+        }
+
+        let mut file = File::open(&pack.pack)?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut reader = BufReader::new(file);
+
+        let mut byte = read_byte(&mut reader)?;
+        let kind = (byte >> 4) & 7;
+        let mut size = (byte & 0x0f) as usize;
+        let mut shift = 4;
+
+        while byte & 0x80 != 0 {
+            byte = read_byte(&mut reader)?;
+            size = varint(size, byte, shift)?;
+            shift += 7;
+        }
+
+        match kind {
+            OBJ_OFS_DELTA => {
+                let mut byte = read_byte(&mut reader)?;
+                let mut distance = (byte & 0x7f) as u64;
+
+                while byte & 0x80 != 0 {
+                    byte = read_byte(&mut reader)?;
+                    distance = distance
+                        .checked_add(1)
+                        .filter(|distance| distance.leading_zeros() >= 7)
+                        .map(|distance| (distance << 7) | (byte & 0x7f) as u64)
+                        .ok_or(GitError::Corrupt("delta offset overflow"))?;
+                }
+
+                let base = offset
+                    .checked_sub(distance)
+                    .ok_or(GitError::Corrupt("delta base out of range"))?;
+
+                let delta = inflate(reader, size)?;
+                let (kind, base) = self.unpack(pack, base, depth + 1)?;
+
+                Ok((kind, apply_delta(&base, &delta)?))
+            }
+            OBJ_REF_DELTA => {
+                let mut base = [0u8; 20];
+                reader.read_exact(&mut base)?;
+
+                let delta = inflate(reader, size)?;
+                let (kind, base) = self
+                    .object(&Sha1Sum(base), depth + 1)?
+                    .ok_or(GitError::Corrupt("missing delta base"))?;
+
+                Ok((kind, apply_delta(&base, &delta)?))
+            }
+            1..=4 => Ok((kind, inflate(reader, size)?)),
+            _ => Err(GitError::Corrupt("unknown packed object type")),
+        }
+    }
+
+    fn object(&self, hash: &Sha1Sum, depth: usize) -> Result<Option<(u8, Vec<u8>)>, GitError> {
+        if let Some(object) = self.loose(hash)? {
+            return Ok(Some(object));
+        }
+
+        self.packed(hash, depth)
+    }
+
+    pub fn blob(&self, hash: &Sha1Sum) -> Result<Option<Vec<u8>>, GitError> {
+        let data = match self.object(hash, 0)? {
+            Some((OBJ_BLOB, data)) => data,
+            _ => return Ok(None),
+        };
+
+        let mut hasher = <Ring as Hasher<Sha1>>::new();
+        hasher.write(format!("blob {}\0", data.len()).as_bytes());
+        hasher.write(&data);
+
+        if &hasher.hash() != hash {
+            return Err(GitError::Corrupt("object hash mismatch"));
+        }
+
+        Ok(Some(data))
+    }
+}
+
+impl ResourceProvider<Sha1> for GitObjectProvider {
+    type Fetch = Ready<Result<Option<Vec<u8>>, GitError>>;
+
+    fn fetch(&self, hash: Sha1Sum) -> Self::Fetch {
+        ready(self.blob(&hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_dir;
+    use flate2::{write::ZlibEncoder, Compression};
+    use std::io::Write;
+
+    fn write_loose(git_dir: &Path, hash: &Sha1Sum, object: &[u8]) {
+        let hex = hash.to_hex();
+        let directory = git_dir.join("objects").join(&hex[..2]);
+
+        fs::create_dir_all(&directory).unwrap();
+
+        fs::write(directory.join(&hex[2..]), compress(object)).unwrap();
+    }
+
+    fn compress(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn entry_header(kind: u8, size: usize) -> Vec<u8> {
+        let mut header = vec![(kind << 4) | (size & 0x0f) as u8];
+        let mut size = size >> 4;
+
+        while size != 0 {
+            *header.last_mut().unwrap() |= 0x80;
+            header.push((size & 0x7f) as u8);
+            size >>= 7;
+        }
+
+        header
+    }
+
+    fn write_pack(git_dir: &Path, entries: Vec<(Sha1Sum, Vec<u8>)>) {
+        let directory = git_dir.join("objects").join("pack");
+        fs::create_dir_all(&directory).unwrap();
+
+        let mut pack = b"PACK".to_vec();
+        pack.extend_from_slice(&2u32.to_be_bytes());
+        pack.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+
+        let mut offsets = vec![];
+
+        for (hash, entry) in entries {
+            offsets.push((hash, pack.len() as u32));
+            pack.extend_from_slice(&entry);
+        }
+
+        pack.extend_from_slice(&[0; 20]);
+        offsets.sort_by(|a, b| a.0 .0.cmp(&b.0 .0));
+
+        let mut index = vec![0xff, b't', b'O', b'c', 0, 0, 0, 2];
+
+        for byte in 0..256 {
+            let count = offsets
+                .iter()
+                .filter(|(hash, _)| hash.0[0] as usize <= byte)
+                .count();
+            index.extend_from_slice(&(count as u32).to_be_bytes());
+        }
+        for (hash, _) in &offsets {
+            index.extend_from_slice(&hash.0);
+        }
+        for _ in &offsets {
+            index.extend_from_slice(&[0; 4]);
+        }
+        for (_, offset) in &offsets {
+            index.extend_from_slice(&offset.to_be_bytes());
+        }
+        index.extend_from_slice(&[0; 40]);
+
+        fs::write(directory.join("pack-test.pack"), pack).unwrap();
+        fs::write(directory.join("pack-test.idx"), index).unwrap();
+    }
+
+    fn object_hash(object: &[u8]) -> Sha1Sum {
+        let mut hasher = <Ring as Hasher<Sha1>>::new();
+        hasher.write(object);
+        hasher.hash()
+    }
+
+    #[test]
+    fn applies_copy_and_insert_ops() {
+        let delta = [11, 11, 0x90, 6, 5, b't', b'h', b'e', b'r', b'e'];
+
+        assert_eq!(
+            apply_delta(b"hello world", &delta).unwrap(),
+            b"hello there".to_vec()
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_deltas() {
+        assert!(matches!(
+            apply_delta(b"base", &[5, 4]),
+            Err(GitError::Corrupt(_))
+        ));
+        assert!(matches!(
+            apply_delta(b"base", &[4, 4, 0x91, 2, 4]),
+            Err(GitError::Corrupt(_))
+        ));
+        assert!(matches!(
+            apply_delta(b"base", &[4, 4, 0]),
+            Err(GitError::Corrupt(_))
+        ));
+        assert!(matches!(
+            delta_size(&[0xff; 11], &mut 0),
+            Err(GitError::Corrupt(_))
+        ));
+        assert!(matches!(
+            apply_delta(b"", &[0, 0xff, 0xff, 0xff, 0xff, 0x7f]),
+            Err(GitError::Corrupt(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_fanout_beyond_count() {
+        let mut index = vec![0xff, b't', b'O', b'c', 0, 0, 0, 2];
+
+        index.extend_from_slice(&5u32.to_be_bytes());
+        for _ in 1..256 {
+            index.extend_from_slice(&1u32.to_be_bytes());
+        }
+
+        let pack = PackIndex {
+            pack: PathBuf::new(),
+            index,
+            count: 1,
+        };
+
+        assert!(matches!(pack.find(&[0; 20]), Err(GitError::Corrupt(_))));
+    }
+
+    #[test]
+    fn reads_verified_loose_blobs() {
+        let git_dir = temp_dir("git-loose");
+        let object = b"blob 5\0hello";
+        let hash = object_hash(object);
+
+        write_loose(&git_dir, &hash, object);
+
+        let provider = GitObjectProvider::open(&git_dir).unwrap();
+
+        assert_eq!(provider.blob(&hash).unwrap(), Some(b"hello".to_vec()));
+        assert_eq!(provider.blob(&Sha1Sum([0; 20])).unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_blobs_with_wrong_hash() {
+        let git_dir = temp_dir("git-mismatch");
+        let hash = object_hash(b"blob 5\0hello");
+
+        write_loose(&git_dir, &hash, b"blob 5\0jello");
+
+        let provider = GitObjectProvider::open(&git_dir).unwrap();
+
+        assert!(matches!(provider.blob(&hash), Err(GitError::Corrupt(_))));
+    }
+
+    #[test]
+    fn rejects_loose_objects_longer_than_their_header() {
+        let git_dir = temp_dir("git-oversized");
+        let hash = object_hash(b"blob 5\0hello");
+
+        write_loose(&git_dir, &hash, b"blob 5\0hello, world");
+
+        let provider = GitObjectProvider::open(&git_dir).unwrap();
+
+        assert!(matches!(provider.blob(&hash), Err(GitError::Corrupt(_))));
+    }
+
+    #[test]
+    fn reads_packed_blobs_through_deltas() {
+        let git_dir = temp_dir("git-pack");
+        let base = object_hash(b"blob 11\0hello world");
+        let offset = object_hash(b"blob 11\0hello there");
+        let reference = object_hash(b"blob 12\0hello world!");
+
+        let mut base_entry = entry_header(OBJ_BLOB, 11);
+        base_entry.extend_from_slice(&compress(b"hello world"));
+
+        let ofs_delta = [11, 11, 0x90, 6, 5, b't', b'h', b'e', b'r', b'e'];
+        let mut ofs_entry = entry_header(OBJ_OFS_DELTA, ofs_delta.len());
+        ofs_entry.push(base_entry.len() as u8);
+        ofs_entry.extend_from_slice(&compress(&ofs_delta));
+
+        let ref_delta = [11, 12, 0x90, 11, 1, b'!'];
+        let mut ref_entry = entry_header(OBJ_REF_DELTA, ref_delta.len());
+        ref_entry.extend_from_slice(&base.0);
+        ref_entry.extend_from_slice(&compress(&ref_delta));
+
+        write_pack(
+            &git_dir,
+            vec![
+                (base, base_entry),
+                (offset, ofs_entry),
+                (reference, ref_entry),
+            ],
+        );
+
+        let provider = GitObjectProvider::open(&git_dir).unwrap();
+
+        assert_eq!(provider.blob(&base).unwrap(), Some(b"hello world".to_vec()));
+        assert_eq!(
+            provider.blob(&offset).unwrap(),
+            Some(b"hello there".to_vec())
+        );
+        assert_eq!(
+            provider.blob(&reference).unwrap(),
+            Some(b"hello world!".to_vec())
+        );
+    }
+}
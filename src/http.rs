@@ -0,0 +1,862 @@
+use crate::{
+    blocking::{default_offload, offload, pool_offload, Offload},
+    resource::{
+        hash::{Algorithm, Digest, Hasher},
+        manager::ResourceManager,
+        provider::ResourceProvider,
+    },
+};
+use core::{
+    any::{Any, TypeId},
+    marker::PhantomData,
+    ops::Range,
+    pin::Pin,
+    time::Duration,
+};
+use futures::{executor::block_on, Future};
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum HttpError {
+    #[error("io error: {0}")]
+    Io(#[source] io::Error),
+    #[error("invalid url {0}")]
+    Url(String),
+    #[error("malformed response")]
+    Malformed,
+    #[error("unexpected status {0}")]
+    Status(u16),
+    #[error("hash mismatch for fetched resource")]
+    Mismatch,
+    #[error("message exceeds size limit")]
+    TooLarge,
+}
+
+impl From<io::Error> for HttpError {
+    fn from(input: io::Error) -> Self {
+        HttpError::Io(input)
+    }
+}
+
+pub enum Conditional {
+    NotModified,
+    Modified(Vec<u8>),
+    NotFound,
+}
+
+struct Response {
+    status: u16,
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+fn read_line<R: BufRead>(reader: &mut R, remaining: &mut usize) -> Result<String, HttpError> {
+    let mut line = vec![];
+    let read = reader
+        .by_ref()
+        .take(*remaining as u64)
+        .read_until(b'\n', &mut line)?;
+    *remaining -= read;
+
+    if line.last() != Some(&b'\n') {
+        return Err(if *remaining == 0 {
+            HttpError::TooLarge
+        } else {
+            HttpError::Malformed
+        });
+    }
+
+    let line = String::from_utf8(line).map_err(|_| HttpError::Malformed)?;
+
+    Ok(line.trim_end_matches(&['\r', '\n'][..]).to_owned())
+}
+
+fn read_head<R: BufRead>(reader: &mut R, limit: usize) -> Result<Vec<String>, HttpError> {
+    let mut lines = vec![];
+    let mut remaining = limit;
+
+    loop {
+        let line = read_line(reader, &mut remaining)?;
+
+        if line.is_empty() {
+            return Ok(lines);
+        }
+
+        lines.push(line);
+    }
+}
+
+fn parse_headers(lines: &[String]) -> HashMap<String, String> {
+    lines
+        .iter()
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, ':');
+            Some((
+                parts.next()?.trim().to_ascii_lowercase(),
+                parts.next()?.trim().to_owned(),
+            ))
+        })
+        .collect()
+}
+
+fn read_chunked<R: BufRead>(
+    reader: &mut R,
+    max_header: usize,
+    max_body: usize,
+) -> Result<Vec<u8>, HttpError> {
+    let mut body = vec![];
+
+    loop {
+        let mut remaining = max_header;
+        let line = read_line(reader, &mut remaining)?;
+
+        let size = line
+            .trim()
+            .split(';')
+            .next()
+            .and_then(|size| usize::from_str_radix(size, 16).ok())
+            .ok_or(HttpError::Malformed)?;
+
+        if size == 0 {
+            read_head(reader, max_header)?;
+            return Ok(body);
+        }
+
+        if body.len().saturating_add(size) > max_body {
+            return Err(HttpError::TooLarge);
+        }
+
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..])?;
+
+        let mut crlf = [0u8; 2];
+        reader.read_exact(&mut crlf)?;
+    }
+}
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_WORKERS: usize = 64;
+const DEFAULT_CONNECTIONS: usize = 256;
+const DEFAULT_MAX_HEADER: usize = 64 * 1024;
+const DEFAULT_MAX_BODY: usize = 256 * 1024 * 1024;
+
+pub struct HttpProvider<A: Algorithm, H: Hasher<A>> {
+    host: Arc<String>,
+    path: Arc<String>,
+    timeout: Option<Duration>,
+    max_header: usize,
+    max_body: usize,
+    offload: Offload,
+    ty: PhantomData<fn() -> (A, H)>,
+}
+
+impl<A: Algorithm, H: Hasher<A>> Clone for HttpProvider<A, H> {
+    fn clone(&self) -> Self {
+        HttpProvider {
+            host: self.host.clone(),
+            path: self.path.clone(),
+            timeout: self.timeout,
+            max_header: self.max_header,
+            max_body: self.max_body,
+            offload: self.offload.clone(),
+            ty: PhantomData,
+        }
+    }
+}
+
+impl<A: Algorithm + 'static, H: Hasher<A> + 'static> HttpProvider<A, H>
+where
+    A::Hash: Digest + PartialEq + Send + 'static,
+{
+    pub fn new(base: &str, algorithm: &str) -> Result<Self, HttpError> {
+        let rest = base
+            .strip_prefix("http://")
+            .ok_or_else(|| HttpError::Url(base.to_owned()))?;
+
+        let (host, path) = match rest.find('/') {
+            Some(idx) => rest.split_at(idx),
+            None => (rest, ""),
+        };
+
+        if host.is_empty() {
+            return Err(HttpError::Url(base.to_owned()));
+        }
+
+        Ok(HttpProvider {
+            host: Arc::new(host.to_owned()),
+            path: Arc::new(format!("{}/{}", path.trim_end_matches('/'), algorithm)),
+            timeout: Some(DEFAULT_TIMEOUT),
+            max_header: DEFAULT_MAX_HEADER,
+            max_body: DEFAULT_MAX_BODY,
+            offload: default_offload(),
+            ty: PhantomData,
+        })
+    }
+
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn max_header(mut self, max_header: usize) -> Self {
+        self.max_header = max_header;
+        self
+    }
+
+    pub fn max_body(mut self, max_body: usize) -> Self {
+        self.max_body = max_body;
+        self
+    }
+
+    pub fn offload(mut self, offload: Offload) -> Self {
+        self.offload = offload;
+        self
+    }
+
+    fn blocking<T: Send + 'static, F: FnOnce(&Self) -> Result<T, HttpError> + Send + 'static>(
+        &self,
+        task: F,
+    ) -> impl Future<Output = Result<T, HttpError>> + Send {
+        let provider = self.clone();
+        let task = offload(self.offload.clone(), move || task(&provider));
+
+        async move { task.await.map_err(io::Error::from)? }
+    }
+
+    fn connect(&self) -> Result<TcpStream, HttpError> {
+        let address = if self.host.contains(':') {
+            self.host.to_string()
+        } else {
+            format!("{}:80", self.host)
+        };
+
+        let mut error = io::Error::new(io::ErrorKind::NotFound, "host did not resolve");
+
+        for address in address.to_socket_addrs()? {
+            let stream = match self.timeout {
+                Some(timeout) => TcpStream::connect_timeout(&address, timeout),
+                None => TcpStream::connect(address),
+            };
+
+            match stream {
+                Ok(stream) => {
+                    stream.set_read_timeout(self.timeout)?;
+                    stream.set_write_timeout(self.timeout)?;
+                    return Ok(stream);
+                }
+                Err(e) => error = e,
+            }
+        }
+
+        Err(error.into())
+    }
+
+    fn request(
+        &self,
+        method: &str,
+        hash: &A::Hash,
+        headers: &[(&str, String)],
+    ) -> Result<Response, HttpError> {
+        let mut stream = self.connect()?;
+
+        let mut request = format!(
+            "{} {}/{} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n",
+            method,
+            self.path,
+            hash.to_hex(),
+            self.host
+        );
+        for (name, value) in headers {
+            request.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes())?;
+
+        let mut reader = BufReader::new(stream);
+        let head = read_head(&mut reader, self.max_header)?;
+
+        let status = head
+            .first()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|status| status.parse().ok())
+            .ok_or(HttpError::Malformed)?;
+        let headers = parse_headers(head.get(1..).unwrap_or(&[]));
+
+        let body = if method == "HEAD" || status == 304 || status == 204 {
+            vec![]
+        } else if headers
+            .get("transfer-encoding")
+            .map(|encoding| encoding.eq_ignore_ascii_case("chunked"))
+            .unwrap_or(false)
+        {
+            read_chunked(&mut reader, self.max_header, self.max_body)?
+        } else if let Some(len) = headers.get("content-length") {
+            let len = len.parse().map_err(|_| HttpError::Malformed)?;
+
+            if len > self.max_body {
+                return Err(HttpError::TooLarge);
+            }
+
+            let mut body = vec![0u8; len];
+            reader.read_exact(&mut body)?;
+            body
+        } else {
+            let mut body = vec![];
+            reader
+                .take(self.max_body as u64 + 1)
+                .read_to_end(&mut body)?;
+
+            if body.len() > self.max_body {
+                return Err(HttpError::TooLarge);
+            }
+
+            body
+        };
+
+        Ok(Response {
+            status,
+            headers,
+            body,
+        })
+    }
+
+    fn verify(&self, hash: &A::Hash, data: Vec<u8>) -> Result<Vec<u8>, HttpError> {
+        let mut hasher = H::new();
+        hasher.write(&data);
+
+        if &hasher.hash() != hash {
+            return Err(HttpError::Mismatch);
+        }
+
+        Ok(data)
+    }
+
+    fn get(&self, hash: &A::Hash) -> Result<Option<Vec<u8>>, HttpError> {
+        let response = self.request("GET", hash, &[])?;
+
+        match response.status {
+            200 => self.verify(hash, response.body).map(Some),
+            404 => Ok(None),
+            status => Err(HttpError::Status(status)),
+        }
+    }
+
+    pub fn contains(&self, hash: A::Hash) -> impl Future<Output = Result<bool, HttpError>> + Send {
+        self.blocking(move |this| match this.request("HEAD", &hash, &[])?.status {
+            200 => Ok(true),
+            404 => Ok(false),
+            status => Err(HttpError::Status(status)),
+        })
+    }
+
+    pub fn fetch_if_none_match(
+        &self,
+        hash: A::Hash,
+        etag: String,
+    ) -> impl Future<Output = Result<Conditional, HttpError>> + Send {
+        self.blocking(move |this| {
+            let response = this.request("GET", &hash, &[("If-None-Match", etag)])?;
+
+            match response.status {
+                200 => this.verify(&hash, response.body).map(Conditional::Modified),
+                304 => Ok(Conditional::NotModified),
+                404 => Ok(Conditional::NotFound),
+                status => Err(HttpError::Status(status)),
+            }
+        })
+    }
+
+    pub fn fetch_range(
+        &self,
+        hash: A::Hash,
+        range: Range<u64>,
+    ) -> impl Future<Output = Result<Option<Vec<u8>>, HttpError>> + Send {
+        self.blocking(move |this| {
+            if range.start >= range.end {
+                return Ok(Some(vec![]));
+            }
+
+            let response = this.request(
+                "GET",
+                &hash,
+                &[("Range", format!("bytes={}-{}", range.start, range.end - 1))],
+            )?;
+
+            match response.status {
+                206 => Ok(Some(response.body)),
+                200 => {
+                    let body = this.verify(&hash, response.body)?;
+                    let start = (range.start as usize).min(body.len());
+                    let end = (range.end as usize).min(body.len());
+                    Ok(Some(body[start..end].to_vec()))
+                }
+                404 => Ok(None),
+                416 => Ok(Some(vec![])),
+                status => Err(HttpError::Status(status)),
+            }
+        })
+    }
+
+    pub fn etag(hash: &A::Hash) -> String {
+        format!("\"{}\"", hash.to_hex())
+    }
+}
+
+impl<A: Algorithm + 'static, H: Hasher<A> + 'static> ResourceProvider<A> for HttpProvider<A, H>
+where
+    A::Hash: Digest + PartialEq + Send + 'static,
+{
+    type Fetch = Pin<Box<dyn Future<Output = Result<Option<Vec<u8>>, HttpError>> + Send>>;
+
+    fn fetch(&self, hash: A::Hash) -> Self::Fetch {
+        Box::pin(self.blocking(move |this| this.get(&hash)))
+    }
+}
+
+type Parser = fn(&str) -> Option<(String, Box<dyn FnMut() -> Box<dyn Any + Send> + Send>)>;
+
+fn parse<A: Algorithm>(
+    hex: &str,
+) -> Option<(String, Box<dyn FnMut() -> Box<dyn Any + Send> + Send>)>
+where
+    A::Hash: Digest + Clone + Send + 'static,
+{
+    let hash = A::Hash::from_hex(hex)?;
+
+    Some((
+        hash.to_hex(),
+        Box::new(move || Box::new(hash.clone()) as Box<dyn Any + Send>),
+    ))
+}
+
+pub struct HttpServer<M: ResourceManager> {
+    manager: M,
+    algorithms: HashMap<String, (TypeId, Parser)>,
+    timeout: Option<Duration>,
+    max_header: usize,
+    max_connections: usize,
+    offload: Offload,
+}
+
+impl<M: ResourceManager> HttpServer<M> {
+    pub fn new(manager: M) -> Self {
+        HttpServer {
+            manager,
+            algorithms: HashMap::new(),
+            timeout: Some(DEFAULT_TIMEOUT),
+            max_header: DEFAULT_MAX_HEADER,
+            max_connections: DEFAULT_CONNECTIONS,
+            offload: pool_offload(DEFAULT_WORKERS),
+        }
+    }
+
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn max_header(mut self, max_header: usize) -> Self {
+        self.max_header = max_header;
+        self
+    }
+
+    pub fn max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = max_connections;
+        self
+    }
+
+    pub fn offload(mut self, offload: Offload) -> Self {
+        self.offload = offload;
+        self
+    }
+
+    pub fn algorithm<A: Algorithm + Any>(mut self, name: &str) -> Self
+    where
+        A::Hash: Digest + Clone + Send + 'static,
+    {
+        self.algorithms
+            .insert(name.to_owned(), (TypeId::of::<A>(), parse::<A>));
+        self
+    }
+
+    pub fn handle(&self, stream: TcpStream) -> impl Future<Output = Result<(), HttpError>> + '_ {
+        async move {
+            stream.set_read_timeout(self.timeout)?;
+            stream.set_write_timeout(self.timeout)?;
+
+            let mut reader = BufReader::new(stream.try_clone()?);
+            let mut stream = stream;
+
+            let head = read_head(&mut reader, self.max_header)?;
+            let mut request = head.first().ok_or(HttpError::Malformed)?.split_whitespace();
+            let headers = parse_headers(head.get(1..).unwrap_or(&[]));
+
+            let method = request.next().ok_or(HttpError::Malformed)?.to_owned();
+            let path = request.next().ok_or(HttpError::Malformed)?.to_owned();
+
+            if method != "GET" && method != "HEAD" {
+                return respond(&mut stream, 405, &[], &[], false);
+            }
+
+            let mut segments = path.trim_start_matches('/').rsplitn(2, '/');
+            let hex = segments.next().unwrap_or("");
+            let algorithm = segments
+                .next()
+                .and_then(|prefix| prefix.rsplit('/').next())
+                .unwrap_or("");
+
+            let (algo, parser) = match self.algorithms.get(algorithm) {
+                Some(entry) => *entry,
+                None => return respond(&mut stream, 404, &[], &[], false),
+            };
+
+            let (hex, hash) = match parser(hex) {
+                Some(parsed) => parsed,
+                None => return respond(&mut stream, 400, &[], &[], false),
+            };
+
+            let etag = format!("\"{}\"", hex);
+            let head_only = method == "HEAD";
+
+            if headers
+                .get("if-none-match")
+                .map(|tags| {
+                    tags.split(',')
+                        .any(|tag| tag.trim() == etag || tag.trim() == "*")
+                })
+                .unwrap_or(false)
+            {
+                return respond(&mut stream, 304, &[("ETag", etag)], &[], true);
+            }
+
+            let data = match self.manager.fetch(algo, hash).await {
+                Ok(Some(data)) => data,
+                Ok(None) => return respond(&mut stream, 404, &[], &[], head_only),
+                Err(_) => return respond(&mut stream, 500, &[], &[], head_only),
+            };
+
+            if let Some(range) = headers.get("range") {
+                return match parse_range(range, data.len() as u64) {
+                    Some(range) => respond(
+                        &mut stream,
+                        206,
+                        &[
+                            ("ETag", etag),
+                            (
+                                "Content-Range",
+                                format!("bytes {}-{}/{}", range.start, range.end - 1, data.len()),
+                            ),
+                        ],
+                        &data[range.start as usize..range.end as usize],
+                        head_only,
+                    ),
+                    None => respond(
+                        &mut stream,
+                        416,
+                        &[("Content-Range", format!("bytes */{}", data.len()))],
+                        &[],
+                        head_only,
+                    ),
+                };
+            }
+
+            respond(&mut stream, 200, &[("ETag", etag)], &data, head_only)
+        }
+    }
+
+    pub fn serve<E: Fn(HttpError) + Send + Sync + 'static>(
+        self,
+        listener: TcpListener,
+        errors: E,
+    ) -> Result<(), HttpError>
+    where
+        M: Send + Sync + 'static,
+    {
+        let server = Arc::new(self);
+        let errors = Arc::new(errors);
+        let active = Arc::new(AtomicUsize::new(0));
+
+        for stream in listener.incoming() {
+            let mut stream = stream?;
+
+            if active.fetch_add(1, Ordering::SeqCst) >= server.max_connections {
+                active.fetch_sub(1, Ordering::SeqCst);
+
+                if let Err(e) = stream
+                    .set_write_timeout(server.timeout)
+                    .map_err(HttpError::from)
+                    .and_then(|_| respond(&mut stream, 503, &[], &[], false))
+                {
+                    errors(e);
+                }
+
+                continue;
+            }
+
+            let (server, errors, active) = (server.clone(), errors.clone(), active.clone());
+
+            (server.offload.clone())(Box::new(move || {
+                if let Err(e) = block_on(server.handle(stream)) {
+                    errors(e);
+                }
+
+                active.fetch_sub(1, Ordering::SeqCst);
+            }));
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_range(range: &str, len: u64) -> Option<Range<u64>> {
+    let range = range.trim().strip_prefix("bytes=")?;
+    let mut parts = range.splitn(2, '-');
+    let start = parts.next()?.trim();
+    let end = parts.next()?.trim();
+
+    let (start, end) = if start.is_empty() {
+        let suffix: u64 = end.parse().ok()?;
+        (len.saturating_sub(suffix), len)
+    } else {
+        let start: u64 = start.parse().ok()?;
+        let end = if end.is_empty() {
+            len
+        } else {
+            end.parse::<u64>().ok()?.saturating_add(1).min(len)
+        };
+        (start, end)
+    };
+
+    if start >= end {
+        return None;
+    }
+
+    Some(start..end)
+}
+
+fn respond(
+    stream: &mut TcpStream,
+    status: u16,
+    headers: &[(&str, String)],
+    body: &[u8],
+    head_only: bool,
+) -> Result<(), HttpError> {
+    let reason = match status {
+        200 => "OK",
+        206 => "Partial Content",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        416 => "Range Not Satisfiable",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    };
+
+    let mut response = format!(
+        "HTTP/1.1 {} {}\r\nConnection: close\r\nContent-Length: {}\r\n",
+        status,
+        reason,
+        body.len()
+    );
+    for (name, value) in headers {
+        response.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    response.push_str("\r\n");
+
+    stream.write_all(response.as_bytes())?;
+    if !head_only {
+        stream.write_all(body)?;
+    }
+    stream.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        resource::{
+            hash::HasherExt,
+            manager::{ProviderMetadata, ResourceRegistrant},
+            store::ResourceStore,
+        },
+        test_util::TestHasher,
+        MemoryStore, Sha256, Sha256Sum, SimpleResourceManager,
+    };
+    use std::{
+        sync::{mpsc, Mutex},
+        thread,
+    };
+
+    const MISMATCHED: Sha256Sum = Sha256Sum([7; 32]);
+
+    fn serve() -> (HttpProvider<Sha256, TestHasher>, Sha256Sum) {
+        serve_with(|server| server, |_| {})
+    }
+
+    fn serve_with<
+        C: FnOnce(HttpServer<SimpleResourceManager>) -> HttpServer<SimpleResourceManager>,
+        E: Fn(HttpError) + Send + Sync + 'static,
+    >(
+        configure: C,
+        errors: E,
+    ) -> (HttpProvider<Sha256, TestHasher>, Sha256Sum) {
+        let store = MemoryStore::<Sha256>::new();
+        let hash = <TestHasher as HasherExt<Sha256>>::hash(b"data".to_vec());
+        block_on(store.put_raw(hash, b"data".to_vec())).unwrap();
+        block_on(store.put_raw(MISMATCHED, b"data".to_vec())).unwrap();
+
+        let mut manager = SimpleResourceManager::new();
+        block_on(ResourceRegistrant::<Sha256, _>::register_provider(
+            &mut manager,
+            store,
+            ProviderMetadata::default(),
+        ))
+        .unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = configure(HttpServer::new(manager).algorithm::<Sha256>("sha256"));
+        thread::spawn(move || server.serve(listener, errors));
+
+        let provider = HttpProvider::new(&format!("http://{}", address), "sha256").unwrap();
+
+        (provider, hash)
+    }
+
+    #[test]
+    fn fetch_hit() {
+        let (provider, hash) = serve();
+
+        assert_eq!(
+            block_on(provider.fetch(hash)).unwrap(),
+            Some(b"data".to_vec())
+        );
+        assert!(block_on(provider.contains(hash)).unwrap());
+    }
+
+    #[test]
+    fn fetch_missing() {
+        let (provider, _) = serve();
+
+        assert_eq!(block_on(provider.fetch(Sha256Sum([0; 32]))).unwrap(), None);
+        assert!(!block_on(provider.contains(Sha256Sum([0; 32]))).unwrap());
+    }
+
+    #[test]
+    fn fetch_mismatch() {
+        let (provider, _) = serve();
+
+        match block_on(provider.fetch(MISMATCHED)) {
+            Err(HttpError::Mismatch) => {}
+            _ => panic!("expected hash mismatch"),
+        }
+    }
+
+    #[test]
+    fn if_none_match_not_modified() {
+        let (provider, hash) = serve();
+        let etag = HttpProvider::<Sha256, TestHasher>::etag(&hash);
+
+        match block_on(provider.fetch_if_none_match(hash, etag)).unwrap() {
+            Conditional::NotModified => {}
+            _ => panic!("expected not modified"),
+        }
+        match block_on(provider.fetch_if_none_match(hash, "\"other\"".to_owned())).unwrap() {
+            Conditional::Modified(data) => assert_eq!(data, b"data"),
+            _ => panic!("expected modified"),
+        }
+    }
+
+    #[test]
+    fn if_none_match_skips_the_manager() {
+        let (provider, _) = serve();
+        let missing = Sha256Sum([0; 32]);
+        let etag = HttpProvider::<Sha256, TestHasher>::etag(&missing);
+
+        match block_on(provider.fetch_if_none_match(missing, etag)).unwrap() {
+            Conditional::NotModified => {}
+            _ => panic!("expected not modified"),
+        }
+    }
+
+    #[test]
+    fn limits_reject_oversized_responses() {
+        let (provider, hash) = serve();
+
+        match block_on(provider.clone().max_body(2).fetch(hash)) {
+            Err(HttpError::TooLarge) => {}
+            _ => panic!("expected oversized body"),
+        }
+        match block_on(provider.max_header(8).fetch(hash)) {
+            Err(HttpError::TooLarge) => {}
+            _ => panic!("expected oversized header"),
+        }
+    }
+
+    #[test]
+    fn idle_connections_time_out() {
+        let (sender, receiver) = mpsc::channel();
+        let sender = Mutex::new(sender);
+        let (provider, _) = serve_with(
+            |server| server.timeout(Some(Duration::from_millis(50))),
+            move |e| {
+                let _ = sender.lock().unwrap().send(e);
+            },
+        );
+
+        let _idle = TcpStream::connect(&*provider.host).unwrap();
+
+        match receiver.recv_timeout(Duration::from_secs(5)) {
+            Ok(HttpError::Io(_)) => {}
+            _ => panic!("expected a timeout"),
+        }
+    }
+
+    #[test]
+    fn connections_beyond_the_limit_are_refused() {
+        let (provider, _) = serve_with(|server| server.max_connections(1), |_| {});
+
+        let _idle = TcpStream::connect(&*provider.host).unwrap();
+        let mut refused = String::new();
+
+        TcpStream::connect(&*provider.host)
+            .unwrap()
+            .read_to_string(&mut refused)
+            .unwrap();
+
+        assert!(refused.starts_with("HTTP/1.1 503"));
+    }
+
+    #[test]
+    fn range_partial() {
+        let (provider, hash) = serve();
+
+        assert_eq!(
+            block_on(provider.fetch_range(hash, 1..3)).unwrap(),
+            Some(b"at".to_vec())
+        );
+    }
+
+    #[test]
+    fn range_unsatisfiable() {
+        let (provider, hash) = serve();
+
+        assert_eq!(
+            block_on(provider.fetch_range(hash, 10..20)).unwrap(),
+            Some(vec![])
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use crate::resource::{
+    hash::{Algorithm, Digest},
+    manager::ResourceManager,
+    ResourceError,
+};
+use futures::Future;
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    convert::Infallible,
+    pin::Pin,
+};
+
+pub(crate) type Key = (TypeId, Vec<u8>);
+
+pub(crate) type KeyedFetch =
+    Pin<Box<dyn Future<Output = Result<Option<Vec<u8>>, ResourceError<Infallible>>> + Send>>;
+
+type HashThunk = Box<dyn FnMut() -> Box<dyn Any + Send> + Send>;
+
+fn extract<A: Algorithm>(hash: &(dyn Any + Send)) -> Option<Vec<u8>>
+where
+    A::Hash: Digest + 'static,
+{
+    hash.downcast_ref::<A::Hash>()
+        .map(|hash| hash.as_bytes().to_vec())
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct Keys(HashMap<TypeId, fn(&(dyn Any + Send)) -> Option<Vec<u8>>>);
+
+impl Keys {
+    pub(crate) fn register<A: Algorithm + Any>(&mut self)
+    where
+        A::Hash: Digest + 'static,
+    {
+        self.0.insert(TypeId::of::<A>(), extract::<A>);
+    }
+
+    pub(crate) fn key(&self, algo: TypeId, hash: &mut HashThunk) -> Option<Key> {
+        self.0
+            .get(&algo)
+            .and_then(|extract| extract(hash().as_ref()))
+            .map(|key| (algo, key))
+    }
+
+    /// Runs `keyed` for registered algorithms and passes every other fetch
+    /// straight through to `manager`.
+    pub(crate) fn fetch<M: ResourceManager, F: FnOnce(Key, HashThunk) -> KeyedFetch>(
+        &self,
+        manager: &M,
+        algo: TypeId,
+        mut hash: HashThunk,
+        keyed: F,
+    ) -> KeyedFetch
+    where
+        M::Fetch: Send + 'static,
+    {
+        match self.key(algo, &mut hash) {
+            Some(key) => keyed(key, hash),
+            None => Box::pin(manager.fetch(algo, hash)),
+        }
+    }
+}
@@ -13,9 +13,13 @@ use futures::{
     task::{Spawn, SpawnError},
     TryFutureExt,
 };
+#[cfg(any(feature = "ring-sha256", feature = "ring-sha1"))]
+use ring::digest::digest;
+#[cfg(feature = "ring-sha1")]
+use ring::digest::SHA1_FOR_LEGACY_USE_ONLY;
 #[cfg(feature = "ring-sha256")]
-use ring::digest::{digest, SHA256};
-use serde::{de::DeserializeOwned, Serialize};
+use ring::digest::SHA256;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_cbor::{from_slice, to_vec, Error as CborError};
 use std::{collections::HashMap, hash::Hash, sync::Arc};
 use thiserror::Error;
@@ -40,11 +44,97 @@ pub use resource::Resource;
 mod memory_store;
 pub use memory_store::MemoryStore;
 
+mod canonical_cbor;
+pub use canonical_cbor::{CanonicalCbor, CanonicalCborError};
+
+#[cfg(any(
+    feature = "json",
+    feature = "msgpack",
+    feature = "bincode",
+    feature = "postcard"
+))]
+mod formats;
+#[cfg(feature = "bincode")]
+pub use formats::Bincode;
+#[cfg(feature = "json")]
+pub use formats::Json;
+#[cfg(feature = "msgpack")]
+pub use formats::MsgPack;
+#[cfg(feature = "postcard")]
+pub use formats::Postcard;
+
+#[cfg(any(feature = "zstd", feature = "deflate"))]
+pub mod compressed;
+#[cfg(any(feature = "zstd", feature = "deflate"))]
+#[doc(inline)]
+pub use compressed::Compressed;
+
+#[cfg(feature = "encrypted")]
+pub mod encrypted;
+#[cfg(feature = "encrypted")]
+#[doc(inline)]
+pub use encrypted::{Encrypted, Keyring};
+
+pub mod versioned;
+#[doc(inline)]
+pub use versioned::Versioned;
+
+pub mod blocking;
+#[doc(inline)]
+pub use blocking::Offload;
+
+mod fs_store;
+pub use fs_store::{FsStore, FsStoreError, HashVerifier};
+
+mod pack_store;
+pub use pack_store::{PackStore, PackStoreError};
+
+#[cfg(feature = "git")]
+mod git;
+#[cfg(feature = "git")]
+pub use git::{GitError, GitObjectProvider};
+
+#[cfg(feature = "oci")]
+pub mod oci;
+#[cfg(feature = "oci")]
+#[doc(inline)]
+pub use oci::OciLayoutProvider;
+
+#[cfg(feature = "bundle")]
+pub mod bundle;
+#[cfg(feature = "bundle")]
+#[doc(inline)]
+pub use bundle::{BundleProvider, BundleWriter};
+
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "http")]
+#[doc(inline)]
+pub use http::{HttpProvider, HttpServer};
+
+mod keyed;
+
+mod coalescing_resource_manager;
+pub use coalescing_resource_manager::{CoalescedError, CoalescingResourceManager};
+
+mod tiered_store;
+pub use tiered_store::{TieredStore, TieredStoreBuilder, TieredStoreError, WritePolicy};
+
+mod caching_resource_manager;
+pub use caching_resource_manager::{CacheStats, CachingResourceManager};
+
 mod simple_resource_manager;
-pub use simple_resource_manager::SimpleResourceManager;
+pub use simple_resource_manager::{
+    ErrorPolicy, FetchStrategy, HedgeTimer, SimpleProviderHandle, SimpleResourceManager,
+    SimpleStoreHandle,
+};
+
+#[cfg(test)]
+mod test_util;
 
 use resource::{
-    hash::{Algorithm, Hasher},
+    domain::Domain,
+    hash::{Algorithm, Digest, Hasher},
     Rehydrate,
 };
 
@@ -59,7 +149,7 @@ macro_rules! with_core {
     };};
 }
 
-#[cfg(feature = "ring-sha256")]
+#[cfg(any(feature = "ring-sha256", feature = "ring-sha1"))]
 pub struct Ring {
     data: Vec<u8>,
 }
@@ -82,7 +172,7 @@ impl Hasher<Sha256> for Ring {
     }
 }
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
 pub struct Sha256Sum(pub [u8; 32]);
 
 #[derive(Clone, Copy)]
@@ -92,6 +182,66 @@ impl Algorithm for Sha256 {
     type Hash = Sha256Sum;
 }
 
+impl Digest for Sha256Sum {
+    fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() != 32 {
+            return None;
+        }
+
+        let mut sum = [0u8; 32];
+        sum.copy_from_slice(data);
+        Some(Sha256Sum(sum))
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub struct Sha1Sum(pub [u8; 20]);
+
+#[derive(Clone, Copy)]
+pub struct Sha1;
+
+impl Algorithm for Sha1 {
+    type Hash = Sha1Sum;
+}
+
+#[cfg(feature = "ring-sha1")]
+impl Hasher<Sha1> for Ring {
+    fn new() -> Self {
+        Ring { data: vec![] }
+    }
+
+    fn write(&mut self, data: &[u8]) {
+        self.data.extend_from_slice(data)
+    }
+
+    fn hash(&self) -> Sha1Sum {
+        let hash = digest(&SHA1_FOR_LEGACY_USE_ONLY, &self.data);
+        let mut sum = [0u8; 20];
+        sum.copy_from_slice(hash.as_ref());
+        Sha1Sum(sum)
+    }
+}
+
+impl Digest for Sha1Sum {
+    fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() != 20 {
+            return None;
+        }
+
+        let mut sum = [0u8; 20];
+        sum.copy_from_slice(data);
+        Some(Sha1Sum(sum))
+    }
+}
+
 pub struct Cbor;
 
 impl<T: DeserializeOwned + Serialize> Rehydrate<T> for Cbor {
@@ -108,6 +258,10 @@ impl<T: DeserializeOwned + Serialize> Rehydrate<T> for Cbor {
     }
 }
 
+impl Domain for Cbor {
+    const TAG: &'static str = "cbor";
+}
+
 pub struct Convert;
 
 impl<T: TryFrom<Vec<u8>> + TryInto<Vec<u8>>> Rehydrate<T> for Convert {
@@ -124,6 +278,10 @@ impl<T: TryFrom<Vec<u8>> + TryInto<Vec<u8>>> Rehydrate<T> for Convert {
     }
 }
 
+impl Domain for Convert {
+    const TAG: &'static str = "convert";
+}
+
 pub struct Core {
     singleton: Singleton,
 }
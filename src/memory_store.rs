@@ -1,7 +1,9 @@
 use crate::{
     resource::{
+        domain::{Domain, Separated},
         hash::{Algorithm, Hasher},
         provider::ResourceProvider,
+        store::ResourceStore,
         Rehydrate,
     },
     Resource,
@@ -40,12 +42,12 @@ impl<A: Algorithm> MemoryStore<A> {
         let data = self.data.clone();
 
         async move {
-            let mut data = data.lock().await;
-
             let item = U::dump(item)
                 .await
                 .map_err(|e| Box::new(e) as Box<dyn Error + Send>)?;
 
+            let mut data = data.lock().await;
+
             let mut hasher = H::new();
 
             hasher.write(&item);
@@ -57,6 +59,17 @@ impl<A: Algorithm> MemoryStore<A> {
             Ok(Resource::new(hash))
         }
     }
+
+    pub fn intern_separated<H: Hasher<A>, T: Domain, U: Rehydrate<T> + Domain>(
+        &mut self,
+        item: T,
+    ) -> impl Future<Output = Result<Resource<T, U, A>, Box<dyn Error + Send>>>
+    where
+        A::Hash: Eq + Hash + Clone,
+        U::DumpError: Error + Send + 'static,
+    {
+        self.intern::<Separated<H, T, U>, T, U>(item)
+    }
 }
 
 impl<A: Algorithm> ResourceProvider<A> for MemoryStore<A>
@@ -76,3 +89,44 @@ where
         })
     }
 }
+
+impl<A: Algorithm> ResourceStore<A> for MemoryStore<A>
+where
+    A::Hash: Hash + Eq + Send + 'static,
+{
+    type Put = Pin<Box<dyn Future<Output = Result<(), Box<dyn Error + Send>>> + Send>>;
+    type Remove = Pin<Box<dyn Future<Output = Result<bool, Box<dyn Error + Send>>> + Send>>;
+    type Contains = Pin<Box<dyn Future<Output = Result<bool, Box<dyn Error + Send>>> + Send>>;
+
+    fn put_raw(&self, hash: A::Hash, item: Vec<u8>) -> Self::Put {
+        let data = self.data.clone();
+
+        Box::pin(async move {
+            let mut data = data.lock().await;
+
+            data.insert(hash, item);
+
+            Ok(())
+        })
+    }
+
+    fn remove(&self, hash: A::Hash) -> Self::Remove {
+        let data = self.data.clone();
+
+        Box::pin(async move {
+            let mut data = data.lock().await;
+
+            Ok(data.remove(&hash).is_some())
+        })
+    }
+
+    fn contains(&self, hash: A::Hash) -> Self::Contains {
+        let data = self.data.clone();
+
+        Box::pin(async move {
+            let data = data.lock().await;
+
+            Ok(data.contains_key(&hash))
+        })
+    }
+}
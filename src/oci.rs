@@ -0,0 +1,316 @@
+use crate::{
+    resource::{
+        graph::{Link, Links},
+        hash::{Digest, Hasher},
+        provider::ResourceProvider,
+        Rehydrate,
+    },
+    Json, Resource, Sha256, Sha256Sum,
+};
+use core::marker::PhantomData;
+use futures::future::{ready, Ready};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use thiserror::Error;
+
+pub const MEDIA_TYPE_IMAGE_INDEX: &str = "application/vnd.oci.image.index.v1+json";
+pub const MEDIA_TYPE_IMAGE_MANIFEST: &str = "application/vnd.oci.image.manifest.v1+json";
+const MEDIA_TYPE_DOCKER_LIST: &str = "application/vnd.docker.distribution.manifest.list.v2+json";
+const MEDIA_TYPE_DOCKER_MANIFEST: &str = "application/vnd.docker.distribution.manifest.v2+json";
+
+#[derive(Debug, Error)]
+pub enum OciError {
+    #[error("io error: {0}")]
+    Io(#[source] io::Error),
+    #[error("invalid index: {0}")]
+    Index(#[source] serde_json::Error),
+    #[error("unsupported digest {0}")]
+    Digest(String),
+    #[error("missing blob {0}")]
+    Missing(String),
+    #[error("hash mismatch for stored blob")]
+    Mismatch,
+}
+
+impl From<io::Error> for OciError {
+    fn from(input: io::Error) -> Self {
+        OciError::Io(input)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Descriptor {
+    pub media_type: String,
+    pub digest: String,
+    pub size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<HashMap<String, String>>,
+}
+
+impl Descriptor {
+    pub fn hash(&self) -> Option<Sha256Sum> {
+        self.digest
+            .strip_prefix("sha256:")
+            .and_then(Sha256Sum::from_hex)
+    }
+
+    pub fn resource<T, U: Rehydrate<T>>(&self) -> Option<Resource<T, U, Sha256>> {
+        self.hash().map(Resource::new)
+    }
+
+    pub fn is_index(&self) -> bool {
+        self.media_type == MEDIA_TYPE_IMAGE_INDEX || self.media_type == MEDIA_TYPE_DOCKER_LIST
+    }
+
+    pub fn is_manifest(&self) -> bool {
+        self.media_type == MEDIA_TYPE_IMAGE_MANIFEST
+            || self.media_type == MEDIA_TYPE_DOCKER_MANIFEST
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageIndex {
+    pub schema_version: u32,
+    pub manifests: Vec<Descriptor>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageManifest {
+    pub schema_version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    pub config: Descriptor,
+    pub layers: Vec<Descriptor>,
+}
+
+pub type ManifestResource = Resource<ImageManifest, Json, Sha256>;
+
+impl Links<Sha256> for ImageManifest {
+    fn links(&self) -> Vec<Link<Sha256>> {
+        Some(&self.config)
+            .into_iter()
+            .chain(self.layers.iter())
+            .filter_map(Descriptor::hash)
+            .map(Link::leaf)
+            .collect()
+    }
+}
+
+pub struct OciLayoutProvider<H: Hasher<Sha256>> {
+    root: Arc<PathBuf>,
+    hasher: PhantomData<fn() -> H>,
+}
+
+impl<H: Hasher<Sha256>> Clone for OciLayoutProvider<H> {
+    fn clone(&self) -> Self {
+        OciLayoutProvider {
+            root: self.root.clone(),
+            hasher: PhantomData,
+        }
+    }
+}
+
+impl<H: Hasher<Sha256>> OciLayoutProvider<H> {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        OciLayoutProvider {
+            root: Arc::new(root.into()),
+            hasher: PhantomData,
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn index(&self) -> Result<ImageIndex, OciError> {
+        let data = fs::read(self.root.join("index.json"))?;
+
+        serde_json::from_slice(&data).map_err(OciError::Index)
+    }
+
+    pub fn manifests(&self) -> Result<Vec<ManifestResource>, OciError> {
+        let mut manifests = vec![];
+        let mut seen = HashSet::new();
+
+        self.collect(self.index()?, &mut seen, &mut manifests)?;
+
+        Ok(manifests)
+    }
+
+    fn collect(
+        &self,
+        index: ImageIndex,
+        seen: &mut HashSet<Sha256Sum>,
+        manifests: &mut Vec<ManifestResource>,
+    ) -> Result<(), OciError> {
+        for descriptor in index.manifests {
+            if !descriptor.is_index() && !descriptor.is_manifest() {
+                continue;
+            }
+
+            let hash = descriptor
+                .hash()
+                .ok_or_else(|| OciError::Digest(descriptor.digest.clone()))?;
+
+            if !seen.insert(hash) {
+                continue;
+            }
+
+            if descriptor.is_manifest() {
+                manifests.push(Resource::new(hash));
+                continue;
+            }
+
+            let data = self
+                .read(&hash)?
+                .ok_or_else(|| OciError::Missing(descriptor.digest.clone()))?;
+            let index = serde_json::from_slice(&data).map_err(OciError::Index)?;
+
+            self.collect(index, seen, manifests)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn roots(&self) -> Result<Vec<Link<Sha256>>, OciError> {
+        Ok(self.manifests()?.iter().map(Link::new).collect::<Vec<_>>())
+    }
+
+    fn read(&self, hash: &Sha256Sum) -> Result<Option<Vec<u8>>, OciError> {
+        let data = match fs::read(self.root.join("blobs").join("sha256").join(hash.to_hex())) {
+            Ok(data) => data,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut hasher = H::new();
+        hasher.write(&data);
+
+        if &hasher.hash() != hash {
+            return Err(OciError::Mismatch);
+        }
+
+        Ok(Some(data))
+    }
+}
+
+impl<H: Hasher<Sha256>> ResourceProvider<Sha256> for OciLayoutProvider<H> {
+    type Fetch = Ready<Result<Option<Vec<u8>>, OciError>>;
+
+    fn fetch(&self, hash: Sha256Sum) -> Self::Fetch {
+        ready(self.read(&hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        resource::hash::HasherExt,
+        test_util::{temp_dir, TestHasher},
+    };
+    use futures::executor::block_on;
+
+    fn blob(root: &Path, media_type: &str, data: &[u8]) -> Descriptor {
+        let hash = <TestHasher as HasherExt<Sha256>>::hash(data.to_vec());
+
+        fs::write(root.join("blobs").join("sha256").join(hash.to_hex()), data).unwrap();
+
+        Descriptor {
+            media_type: media_type.to_owned(),
+            digest: format!("sha256:{}", hash.to_hex()),
+            size: data.len() as u64,
+            annotations: None,
+        }
+    }
+
+    fn index(manifests: Vec<Descriptor>) -> Vec<u8> {
+        serde_json::to_vec(&ImageIndex {
+            schema_version: 2,
+            manifests,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn manifests_traverse_nested_indexes() {
+        let root = temp_dir("oci-nested");
+
+        fs::create_dir_all(root.join("blobs").join("sha256")).unwrap();
+        fs::write(
+            root.join("oci-layout"),
+            br#"{"imageLayoutVersion":"1.0.0"}"#,
+        )
+        .unwrap();
+
+        let config = blob(&root, "application/vnd.oci.image.config.v1+json", b"{}");
+        let layer = blob(&root, "application/vnd.oci.image.layer.v1.tar", b"layer");
+        let manifest = ImageManifest {
+            schema_version: 2,
+            media_type: Some(MEDIA_TYPE_IMAGE_MANIFEST.to_owned()),
+            config,
+            layers: vec![layer],
+        };
+        let manifest = blob(
+            &root,
+            MEDIA_TYPE_IMAGE_MANIFEST,
+            &serde_json::to_vec(&manifest).unwrap(),
+        );
+        let attestation = blob(&root, "application/vnd.in-toto+json", b"{}");
+        let nested = blob(
+            &root,
+            MEDIA_TYPE_IMAGE_INDEX,
+            &index(vec![manifest.clone(), attestation]),
+        );
+
+        fs::write(
+            root.join("index.json"),
+            index(vec![nested, manifest.clone()]),
+        )
+        .unwrap();
+
+        let provider = OciLayoutProvider::<TestHasher>::new(&root);
+        let manifests = provider.manifests().unwrap();
+
+        assert_eq!(manifests.len(), 1);
+        assert!(manifests[0].hash() == manifest.hash().unwrap());
+
+        let data = block_on(provider.fetch(manifests[0].hash()))
+            .unwrap()
+            .unwrap();
+        let fetched: ImageManifest = serde_json::from_slice(&data).unwrap();
+
+        assert_eq!(fetched.layers[0].size, 5);
+    }
+
+    #[test]
+    fn missing_nested_index_is_an_error() {
+        let root = temp_dir("oci-missing");
+        let digest = format!("sha256:{}", Sha256Sum([0; 32]).to_hex());
+
+        fs::create_dir_all(root.join("blobs").join("sha256")).unwrap();
+        fs::write(
+            root.join("index.json"),
+            index(vec![Descriptor {
+                media_type: MEDIA_TYPE_IMAGE_INDEX.to_owned(),
+                digest,
+                size: 0,
+                annotations: None,
+            }]),
+        )
+        .unwrap();
+
+        let provider = OciLayoutProvider::<TestHasher>::new(&root);
+
+        assert!(matches!(provider.manifests(), Err(OciError::Missing(_))));
+    }
+}
@@ -0,0 +1,867 @@
+use crate::{
+    blocking::{default_offload, offload, Offload},
+    resource::{
+        hash::{Algorithm, Digest, Hasher},
+        provider::ResourceProvider,
+        store::ResourceStore,
+    },
+};
+use core::{hash::Hash, marker::PhantomData};
+use futures::Future;
+use serde::{Deserialize, Serialize};
+use serde_cbor::{from_slice, to_vec, Error as CborError};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, ErrorKind, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+use thiserror::Error;
+
+const SEGMENT_LIMIT: u64 = 256 * 1024 * 1024;
+const SYNC_INTERVAL: usize = 64;
+const CHECKPOINT_INTERVAL: usize = 16 * 1024;
+
+const LOG_PUT: u8 = 0;
+const LOG_REMOVE: u8 = 1;
+
+#[derive(Debug, Error)]
+pub enum PackStoreError {
+    #[error("io error: {0}")]
+    Io(#[source] io::Error),
+    #[error("index error: {0}")]
+    Index(#[source] CborError),
+    #[error("hash mismatch for stored resource")]
+    Mismatch,
+}
+
+impl From<io::Error> for PackStoreError {
+    fn from(input: io::Error) -> Self {
+        PackStoreError::Io(input)
+    }
+}
+
+impl From<CborError> for PackStoreError {
+    fn from(input: CborError) -> Self {
+        PackStoreError::Index(input)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Location {
+    segment: u64,
+    offset: u64,
+    len: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexFile {
+    segment: u64,
+    end: u64,
+    entries: Vec<(Vec<u8>, u64, u64, u64)>,
+}
+
+struct State<H> {
+    index: HashMap<H, Location>,
+    segment: u64,
+    end: u64,
+    writer: Option<(u64, File)>,
+    reader: Option<(u64, File)>,
+    log: File,
+    logged: usize,
+    unsynced: usize,
+}
+
+pub struct PackStore<A: Algorithm, H: Hasher<A>> {
+    root: Arc<PathBuf>,
+    state: Arc<Mutex<State<A::Hash>>>,
+    offload: Offload,
+    ty: PhantomData<fn() -> H>,
+}
+
+impl<A: Algorithm, H: Hasher<A>> Clone for PackStore<A, H> {
+    fn clone(&self) -> Self {
+        PackStore {
+            root: self.root.clone(),
+            state: self.state.clone(),
+            offload: self.offload.clone(),
+            ty: PhantomData,
+        }
+    }
+}
+
+fn segment_path(root: &Path, segment: u64) -> PathBuf {
+    root.join(format!("segment-{:08}.pack", segment))
+}
+
+fn segments(root: &Path) -> Result<Vec<u64>, PackStoreError> {
+    let mut segments = vec![];
+
+    for entry in fs::read_dir(root)? {
+        let name = entry?.file_name();
+
+        if let Some(segment) = name
+            .to_str()
+            .and_then(|name| name.strip_prefix("segment-"))
+            .and_then(|name| name.strip_suffix(".pack"))
+            .and_then(|name| name.parse().ok())
+        {
+            segments.push(segment);
+        }
+    }
+
+    segments.sort();
+
+    Ok(segments)
+}
+
+fn log_record(hash: &[u8], location: Option<Location>) -> Vec<u8> {
+    let mut record = Vec::with_capacity(2 + hash.len() + 24);
+
+    record.push(if location.is_some() {
+        LOG_PUT
+    } else {
+        LOG_REMOVE
+    });
+    record.push(hash.len() as u8);
+    record.extend_from_slice(hash);
+
+    if let Some(location) = location {
+        for field in &[location.segment, location.offset, location.len] {
+            record.extend_from_slice(&field.to_be_bytes());
+        }
+    }
+
+    record
+}
+
+fn parse_log_record(data: &[u8]) -> Option<(usize, &[u8], Option<Location>)> {
+    let tag = *data.get(0)?;
+    let hash_len = *data.get(1)? as usize;
+    let hash = data.get(2..2 + hash_len)?;
+    let mut position = 2 + hash_len;
+
+    let location = match tag {
+        LOG_PUT => {
+            let mut fields = [0u64; 3];
+
+            for field in &mut fields {
+                let mut buffer = [0u8; 8];
+                buffer.copy_from_slice(data.get(position..position + 8)?);
+                *field = u64::from_be_bytes(buffer);
+                position += 8;
+            }
+
+            Some(Location {
+                segment: fields[0],
+                offset: fields[1],
+                len: fields[2],
+            })
+        }
+        LOG_REMOVE => None,
+        _ => return None,
+    };
+
+    Some((position, hash, location))
+}
+
+fn read_exact_or_eof<R: Read>(reader: &mut R, buffer: &mut [u8]) -> io::Result<bool> {
+    match reader.read_exact(buffer) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+impl<A: Algorithm, H: Hasher<A>> PackStore<A, H>
+where
+    A::Hash: Digest + Eq + Hash + Clone,
+{
+    pub fn open<P: Into<PathBuf>>(root: P) -> Result<Self, PackStoreError> {
+        let root = root.into();
+
+        fs::create_dir_all(&root)?;
+
+        let mut index = HashMap::new();
+        let mut segment = 0;
+        let mut end = 0;
+
+        match fs::read(root.join("index")) {
+            Ok(data) => {
+                let checkpoint: IndexFile = from_slice(&data)?;
+
+                for (hash, segment, offset, len) in checkpoint.entries {
+                    if let Some(hash) = A::Hash::from_bytes(&hash) {
+                        index.insert(
+                            hash,
+                            Location {
+                                segment,
+                                offset,
+                                len,
+                            },
+                        );
+                    }
+                }
+
+                segment = checkpoint.segment;
+                end = checkpoint.end;
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let logged = Self::replay(&root, &mut index, &mut segment, &mut end)?;
+
+        let referenced = index
+            .values()
+            .map(|location| location.segment)
+            .collect::<HashSet<_>>();
+
+        let mut recovered = false;
+
+        for existing in segments(&root)? {
+            if existing < segment {
+                if !referenced.contains(&existing) {
+                    fs::remove_file(segment_path(&root, existing))?;
+                }
+                continue;
+            }
+
+            let start = if existing == segment { end } else { 0 };
+
+            end = Self::recover(&root, existing, start, &mut index)?;
+            segment = existing;
+            recovered |= end > start;
+        }
+
+        let log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(root.join("index.log"))?;
+
+        let store = PackStore {
+            root: Arc::new(root),
+            state: Arc::new(Mutex::new(State {
+                index,
+                segment,
+                end,
+                writer: None,
+                reader: None,
+                log,
+                logged,
+                unsynced: 0,
+            })),
+            offload: default_offload(),
+            ty: PhantomData,
+        };
+
+        if recovered {
+            store.flush()?;
+        }
+
+        Ok(store)
+    }
+
+    pub fn with_offload(mut self, offload: Offload) -> Self {
+        self.offload = offload;
+        self
+    }
+
+    fn run<T: Send + 'static, F: FnOnce(&Self) -> Result<T, PackStoreError> + Send + 'static>(
+        &self,
+        task: F,
+    ) -> Pin<Box<dyn Future<Output = Result<T, PackStoreError>> + Send>>
+    where
+        A: 'static,
+        A::Hash: Send,
+        H: 'static,
+    {
+        let store = self.clone();
+
+        let task = offload(self.offload.clone(), move || task(&store));
+
+        Box::pin(async move { task.await.map_err(io::Error::from)? })
+    }
+
+    fn replay(
+        root: &Path,
+        index: &mut HashMap<A::Hash, Location>,
+        segment: &mut u64,
+        end: &mut u64,
+    ) -> Result<usize, PackStoreError> {
+        let path = root.join("index.log");
+
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut position = 0;
+        let mut logged = 0;
+
+        while let Some((len, hash, location)) = parse_log_record(&data[position..]) {
+            position += len;
+            logged += 1;
+
+            let hash = match A::Hash::from_bytes(hash) {
+                Some(hash) => hash,
+                None => continue,
+            };
+
+            match location {
+                Some(location) => {
+                    let tail = location.offset + location.len;
+
+                    if (location.segment, tail) > (*segment, *end) {
+                        *segment = location.segment;
+                        *end = tail;
+                    }
+
+                    index.insert(hash, location);
+                }
+                None => {
+                    index.remove(&hash);
+                }
+            }
+        }
+
+        if position < data.len() {
+            OpenOptions::new()
+                .write(true)
+                .open(&path)?
+                .set_len(position as u64)?;
+        }
+
+        Ok(logged)
+    }
+
+    fn recover(
+        root: &Path,
+        segment: u64,
+        start: u64,
+        index: &mut HashMap<A::Hash, Location>,
+    ) -> Result<u64, PackStoreError> {
+        let path = segment_path(root, segment);
+        let mut file = File::open(&path)?;
+        let total = file.metadata()?.len();
+
+        if start > total {
+            return Ok(total);
+        }
+
+        file.seek(SeekFrom::Start(start))?;
+        let mut reader = BufReader::new(file);
+        let mut position = start;
+
+        while position < total {
+            let mut hash_len = [0u8; 1];
+            if !read_exact_or_eof(&mut reader, &mut hash_len)? {
+                break;
+            }
+
+            let mut hash = vec![0u8; hash_len[0] as usize];
+            let mut len = [0u8; 8];
+            if !read_exact_or_eof(&mut reader, &mut hash)?
+                || !read_exact_or_eof(&mut reader, &mut len)?
+            {
+                break;
+            }
+            let len = u64::from_be_bytes(len);
+
+            let offset = position + 1 + hash.len() as u64 + 8;
+            if offset.checked_add(len).map(|end| end > total) != Some(false) {
+                break;
+            }
+
+            let mut data = vec![0u8; len as usize];
+            if !read_exact_or_eof(&mut reader, &mut data)? {
+                break;
+            }
+
+            let mut hasher = H::new();
+            hasher.write(&data);
+            let computed = hasher.hash();
+
+            match A::Hash::from_bytes(&hash) {
+                Some(hash) if hash == computed => {
+                    index.insert(
+                        hash,
+                        Location {
+                            segment,
+                            offset,
+                            len,
+                        },
+                    );
+                }
+                _ => break,
+            }
+
+            position = offset + len;
+        }
+
+        if position < total {
+            OpenOptions::new()
+                .write(true)
+                .open(&path)?
+                .set_len(position)?;
+        }
+
+        Ok(position)
+    }
+
+    fn sync(state: &mut State<A::Hash>) -> Result<(), PackStoreError> {
+        if let Some((_, writer)) = &state.writer {
+            writer.sync_data()?;
+        }
+
+        state.log.sync_data()?;
+        state.unsynced = 0;
+
+        Ok(())
+    }
+
+    fn checkpoint(&self, state: &mut State<A::Hash>) -> Result<(), PackStoreError> {
+        Self::sync(state)?;
+
+        let index = IndexFile {
+            segment: state.segment,
+            end: state.end,
+            entries: state
+                .index
+                .iter()
+                .map(|(hash, location)| {
+                    (
+                        hash.as_bytes().to_vec(),
+                        location.segment,
+                        location.offset,
+                        location.len,
+                    )
+                })
+                .collect(),
+        };
+
+        let temporary = self.root.join("index.tmp");
+
+        let mut file = File::create(&temporary)?;
+        file.write_all(&to_vec(&index)?)?;
+        file.sync_all()?;
+        fs::rename(&temporary, self.root.join("index"))?;
+
+        state.log.set_len(0)?;
+        state.log.sync_data()?;
+        state.logged = 0;
+
+        Ok(())
+    }
+
+    fn log(
+        &self,
+        state: &mut State<A::Hash>,
+        hash: &A::Hash,
+        location: Option<Location>,
+    ) -> Result<(), PackStoreError> {
+        state
+            .log
+            .write_all(&log_record(hash.as_bytes(), location))?;
+        state.logged += 1;
+        state.unsynced += 1;
+
+        if state.logged >= CHECKPOINT_INTERVAL {
+            self.checkpoint(state)
+        } else if state.unsynced >= SYNC_INTERVAL {
+            Self::sync(state)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn append(
+        &self,
+        state: &mut State<A::Hash>,
+        hash: &A::Hash,
+        data: &[u8],
+    ) -> Result<Location, PackStoreError> {
+        let hash_bytes = hash.as_bytes();
+        let size = 1 + hash_bytes.len() as u64 + 8 + data.len() as u64;
+
+        if state.end > 0 && state.end + size > SEGMENT_LIMIT {
+            state.segment += 1;
+            state.end = 0;
+        }
+
+        let mut record = Vec::with_capacity(size as usize);
+        record.push(hash_bytes.len() as u8);
+        record.extend_from_slice(hash_bytes);
+        record.extend_from_slice(&(data.len() as u64).to_be_bytes());
+        record.extend_from_slice(data);
+
+        if state.writer.as_ref().map(|(segment, _)| *segment) != Some(state.segment) {
+            if let Some((_, writer)) = state.writer.take() {
+                writer.sync_data()?;
+            }
+
+            let path = segment_path(&self.root, state.segment);
+            let file = OpenOptions::new().create(true).write(true).open(&path)?;
+            state.writer = Some((state.segment, file));
+        }
+
+        let end = state.end;
+        let written = state
+            .writer
+            .as_mut()
+            .map(|(_, writer)| {
+                writer
+                    .seek(SeekFrom::Start(end))
+                    .and_then(|_| writer.write_all(&record))
+            })
+            .unwrap();
+
+        if let Err(e) = written {
+            state.writer = None;
+            return Err(e.into());
+        }
+
+        let location = Location {
+            segment: state.segment,
+            offset: state.end + size - data.len() as u64,
+            len: data.len() as u64,
+        };
+
+        state.end += size;
+
+        Ok(location)
+    }
+
+    fn load(
+        &self,
+        state: &mut State<A::Hash>,
+        location: Location,
+    ) -> Result<Vec<u8>, PackStoreError> {
+        if state.reader.as_ref().map(|(segment, _)| *segment) != Some(location.segment) {
+            state.reader = None;
+
+            let file = File::open(segment_path(&self.root, location.segment))?;
+            state.reader = Some((location.segment, file));
+        }
+
+        let mut data = vec![0u8; location.len as usize];
+        let read = state
+            .reader
+            .as_mut()
+            .map(|(_, reader)| {
+                reader
+                    .seek(SeekFrom::Start(location.offset))
+                    .and_then(|_| reader.read_exact(&mut data))
+            })
+            .unwrap();
+
+        if let Err(e) = read {
+            state.reader = None;
+            return Err(e.into());
+        }
+
+        Ok(data)
+    }
+
+    fn read(&self, hash: &A::Hash) -> Result<Option<Vec<u8>>, PackStoreError> {
+        let data = {
+            let mut state = self.state.lock().unwrap();
+
+            let location = match state.index.get(hash) {
+                Some(location) => *location,
+                None => return Ok(None),
+            };
+
+            self.load(&mut state, location)?
+        };
+
+        let mut hasher = H::new();
+        hasher.write(&data);
+
+        if &hasher.hash() != hash {
+            return Err(PackStoreError::Mismatch);
+        }
+
+        Ok(Some(data))
+    }
+
+    fn write(&self, hash: A::Hash, data: &[u8]) -> Result<(), PackStoreError> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.index.contains_key(&hash) {
+            return Ok(());
+        }
+
+        let location = self.append(&mut state, &hash, data)?;
+        state.index.insert(hash.clone(), location);
+
+        self.log(&mut state, &hash, Some(location))
+    }
+
+    fn delete(&self, hash: &A::Hash) -> Result<bool, PackStoreError> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.index.remove(hash).is_none() {
+            return Ok(false);
+        }
+
+        self.log(&mut state, hash, None)?;
+
+        Ok(true)
+    }
+
+    pub fn flush(&self) -> Result<(), PackStoreError> {
+        let mut state = self.state.lock().unwrap();
+
+        self.checkpoint(&mut state)
+    }
+
+    pub fn hashes(&self) -> Vec<A::Hash> {
+        self.state.lock().unwrap().index.keys().cloned().collect()
+    }
+
+    pub fn compact(
+        &self,
+        live: HashSet<A::Hash>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), PackStoreError>> + Send>>
+    where
+        A: 'static,
+        A::Hash: Send,
+        H: 'static,
+    {
+        self.run(move |store| store.rewrite(&live))
+    }
+
+    fn rewrite(&self, live: &HashSet<A::Hash>) -> Result<(), PackStoreError> {
+        let mut state = self.state.lock().unwrap();
+
+        let previous = state.segment;
+        let entries = state
+            .index
+            .iter()
+            .filter(|(hash, _)| live.contains(*hash))
+            .map(|(hash, location)| (hash.clone(), *location))
+            .collect::<Vec<_>>();
+
+        state.segment += 1;
+        state.end = 0;
+
+        let mut index = HashMap::with_capacity(entries.len());
+
+        for (hash, location) in entries {
+            let data = self.load(&mut state, location)?;
+
+            let location = self.append(&mut state, &hash, &data)?;
+            index.insert(hash, location);
+        }
+
+        state.index = index;
+        state.reader = None;
+        self.checkpoint(&mut state)?;
+
+        for segment in segments(&self.root)? {
+            if segment <= previous {
+                fs::remove_file(segment_path(&self.root, segment))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<A: Algorithm, H: Hasher<A> + 'static> ResourceProvider<A> for PackStore<A, H>
+where
+    A: 'static,
+    A::Hash: Digest + Eq + Hash + Clone + Send + 'static,
+{
+    type Fetch = Pin<Box<dyn Future<Output = Result<Option<Vec<u8>>, PackStoreError>> + Send>>;
+
+    fn fetch(&self, hash: A::Hash) -> Self::Fetch {
+        self.run(move |store| store.read(&hash))
+    }
+}
+
+impl<A: Algorithm, H: Hasher<A> + 'static> ResourceStore<A> for PackStore<A, H>
+where
+    A: 'static,
+    A::Hash: Digest + Eq + Hash + Clone + Send + 'static,
+{
+    type Put = Pin<Box<dyn Future<Output = Result<(), PackStoreError>> + Send>>;
+    type Remove = Pin<Box<dyn Future<Output = Result<bool, PackStoreError>> + Send>>;
+    type Contains = Pin<Box<dyn Future<Output = Result<bool, PackStoreError>> + Send>>;
+
+    fn put_raw(&self, hash: A::Hash, data: Vec<u8>) -> Self::Put {
+        self.run(move |store| store.write(hash, &data))
+    }
+
+    fn remove(&self, hash: A::Hash) -> Self::Remove {
+        self.run(move |store| store.delete(&hash))
+    }
+
+    fn contains(&self, hash: A::Hash) -> Self::Contains {
+        let contains = self.state.lock().unwrap().index.contains_key(&hash);
+
+        Box::pin(async move { Ok(contains) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        resource::hash::HasherExt,
+        test_util::{temp_dir, TestHasher},
+        Sha256, Sha256Sum,
+    };
+    use futures::executor::block_on;
+
+    type Store = PackStore<Sha256, TestHasher>;
+
+    fn hash(data: &[u8]) -> Sha256Sum {
+        <TestHasher as HasherExt<Sha256>>::hash(data.to_vec())
+    }
+
+    fn append(path: &Path, data: &[u8]) -> u64 {
+        let mut file = OpenOptions::new().append(true).open(path).unwrap();
+        let len = file.metadata().unwrap().len();
+        file.write_all(data).unwrap();
+        len
+    }
+
+    #[test]
+    fn replays_log_without_checkpoint() {
+        let root = temp_dir("pack-log");
+
+        {
+            let store = Store::open(&root).unwrap();
+
+            store.write(hash(b"kept"), b"kept").unwrap();
+            store.write(hash(b"removed"), b"removed").unwrap();
+            assert!(store.delete(&hash(b"removed")).unwrap());
+        }
+
+        assert!(!root.join("index").exists());
+
+        let store = Store::open(&root).unwrap();
+
+        assert_eq!(store.read(&hash(b"kept")).unwrap(), Some(b"kept".to_vec()));
+        assert_eq!(store.read(&hash(b"removed")).unwrap(), None);
+    }
+
+    #[test]
+    fn recovers_from_truncated_tails() {
+        let root = temp_dir("pack-truncated");
+        let segment = segment_path(&root, 0);
+        let log = root.join("index.log");
+
+        {
+            let store = Store::open(&root).unwrap();
+
+            store.write(hash(b"first"), b"first").unwrap();
+            store.flush().unwrap();
+            store.write(hash(b"second"), b"second").unwrap();
+        }
+
+        let segment_len = append(&segment, &[32, 1, 2, 3]);
+        let log_len = append(&log, &[LOG_PUT, 32, 9]);
+
+        {
+            let store = Store::open(&root).unwrap();
+
+            assert_eq!(fs::metadata(&segment).unwrap().len(), segment_len);
+            assert_eq!(fs::metadata(&log).unwrap().len(), log_len);
+            assert_eq!(
+                store.read(&hash(b"first")).unwrap(),
+                Some(b"first".to_vec())
+            );
+            assert_eq!(
+                store.read(&hash(b"second")).unwrap(),
+                Some(b"second".to_vec())
+            );
+
+            store.write(hash(b"third"), b"third").unwrap();
+        }
+
+        let store = Store::open(&root).unwrap();
+
+        assert_eq!(store.hashes().len(), 3);
+        assert_eq!(
+            store.read(&hash(b"third")).unwrap(),
+            Some(b"third".to_vec())
+        );
+    }
+
+    #[test]
+    fn recovers_unlogged_segment_records() {
+        let root = temp_dir("pack-unlogged");
+
+        {
+            let store = Store::open(&root).unwrap();
+
+            store.write(hash(b"logged"), b"logged").unwrap();
+        }
+
+        fs::write(root.join("index.log"), b"").unwrap();
+
+        {
+            let store = Store::open(&root).unwrap();
+
+            assert_eq!(
+                store.read(&hash(b"logged")).unwrap(),
+                Some(b"logged".to_vec())
+            );
+
+            store.write(hash(b"later"), b"later").unwrap();
+        }
+
+        let store = Store::open(&root).unwrap();
+
+        assert_eq!(store.hashes().len(), 2);
+    }
+
+    #[test]
+    fn compaction_drops_dead_entries() {
+        let root = temp_dir("pack-compact");
+        let store = Store::open(&root).unwrap();
+
+        store.write(hash(b"live"), b"live").unwrap();
+        store.write(hash(b"dead"), b"dead").unwrap();
+
+        let live = vec![hash(b"live")].into_iter().collect();
+        block_on(store.compact(live)).unwrap();
+
+        assert_eq!(segments(&root).unwrap(), vec![1]);
+        drop(store);
+
+        let store = Store::open(&root).unwrap();
+
+        assert_eq!(store.read(&hash(b"live")).unwrap(), Some(b"live".to_vec()));
+        assert_eq!(store.read(&hash(b"dead")).unwrap(), None);
+    }
+
+    #[test]
+    fn provider_reads_survive_compaction() {
+        let root = temp_dir("pack-provider");
+        let store = Store::open(&root).unwrap();
+
+        block_on(store.put_raw(hash(b"live"), b"live".to_vec())).unwrap();
+        block_on(store.put_raw(hash(b"dead"), b"dead".to_vec())).unwrap();
+
+        assert_eq!(
+            block_on(store.fetch(hash(b"dead"))).unwrap(),
+            Some(b"dead".to_vec())
+        );
+
+        let live = vec![hash(b"live")].into_iter().collect();
+        block_on(store.compact(live)).unwrap();
+
+        assert_eq!(
+            block_on(store.fetch(hash(b"live"))).unwrap(),
+            Some(b"live".to_vec())
+        );
+        assert!(!block_on(store.contains(hash(b"dead"))).unwrap());
+    }
+}
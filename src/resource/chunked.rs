@@ -0,0 +1,359 @@
+use super::{
+    hash::{Algorithm, Hasher},
+    manager::ResourceManager,
+    ErasedResourceManager, Rehydrate, ResourceError, ResourceManagerExt,
+};
+use crate::{acquire, Convert, CoreError, Resource};
+use core::{
+    any::{Any, TypeId},
+    convert::Infallible,
+    hash::Hash,
+    marker::PhantomData,
+};
+use core_error::Error;
+use fastcdc::v2020::FastCDC;
+use futures::{future::try_join_all, Future};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_cbor::{from_slice, to_vec, Error as CborError};
+use std::pin::Pin;
+use thiserror::Error;
+
+const MIN_CHUNK: u32 = 16 * 1024;
+const AVERAGE_CHUNK: u32 = 64 * 1024;
+const MAX_CHUNK: u32 = 256 * 1024;
+
+#[derive(Debug, Error)]
+#[bounds(where T: Error + 'static)]
+pub enum ChunkedError<T> {
+    #[error("no active resource manager")]
+    NoResourceManager,
+    #[error("missing chunk")]
+    MissingChunk,
+    #[error("chunk hash mismatch")]
+    Mismatch,
+    #[error("manifest length does not match chunks")]
+    Length,
+    #[error("core error: {0}")]
+    Core(#[source] CoreError),
+    #[error("resource error: {0}")]
+    Resource(#[source] ResourceError<Infallible>),
+    #[error("manifest error: {0}")]
+    Manifest(#[source] CborError),
+    #[error("inner error: {0}")]
+    Inner(#[source] T),
+}
+
+#[derive(Serialize, Deserialize)]
+struct Manifest<H> {
+    len: u64,
+    chunks: Vec<H>,
+}
+
+pub(crate) struct Split<H> {
+    pub(crate) chunks: Vec<(H, Vec<u8>)>,
+    pub(crate) manifest: Vec<u8>,
+}
+
+pub(crate) fn split<A: Algorithm, H: Hasher<A>>(data: &[u8]) -> Result<Split<A::Hash>, CborError>
+where
+    A::Hash: Serialize + Clone,
+{
+    let chunks = FastCDC::new(data, MIN_CHUNK, AVERAGE_CHUNK, MAX_CHUNK)
+        .map(|chunk| {
+            let chunk = data[chunk.offset..chunk.offset + chunk.length].to_vec();
+            let mut hasher = H::new();
+            hasher.write(&chunk);
+            (hasher.hash(), chunk)
+        })
+        .collect::<Vec<_>>();
+
+    let manifest = to_vec(&Manifest {
+        len: data.len() as u64,
+        chunks: chunks
+            .iter()
+            .map(|(hash, _)| hash.clone())
+            .collect::<Vec<_>>(),
+    })?;
+
+    Ok(Split { chunks, manifest })
+}
+
+pub struct Chunked<U, A, H>(PhantomData<(U, A, H)>);
+
+impl<T, U, A, H> Rehydrate<T> for Chunked<U, A, H>
+where
+    T: Send + 'static,
+    U: Rehydrate<T>,
+    U::Rehydrate: Send + 'static,
+    U::RehydrateError: Send + 'static,
+    U::Dump: Send + 'static,
+    U::DumpError: Send + 'static,
+    A: Algorithm + Any + Send + 'static,
+    A::Hash: Serialize + DeserializeOwned + Eq + Hash + Clone + Send + 'static,
+    H: Hasher<A>,
+{
+    type RehydrateError = ChunkedError<U::RehydrateError>;
+    type Rehydrate = Pin<Box<dyn Future<Output = Result<T, Self::RehydrateError>> + Send>>;
+    type DumpError = ChunkedError<U::DumpError>;
+    type Dump = Pin<Box<dyn Future<Output = Result<Vec<u8>, Self::DumpError>> + Send>>;
+
+    fn rehydrate(data: Vec<u8>) -> Self::Rehydrate {
+        let manager = acquire::<ErasedResourceManager>();
+
+        Box::pin(async move {
+            let manifest: Manifest<A::Hash> = from_slice(&data).map_err(ChunkedError::Manifest)?;
+
+            let manager = manager
+                .await
+                .map_err(ChunkedError::Core)?
+                .ok_or(ChunkedError::NoResourceManager)?;
+
+            let fetches = manifest
+                .chunks
+                .iter()
+                .cloned()
+                .map(|hash| {
+                    ResourceManagerExt::fetch(&manager, Resource::<Vec<u8>, Convert, A>::new(hash))
+                })
+                .collect::<Vec<_>>();
+
+            let chunks = try_join_all(fetches)
+                .await
+                .map_err(ChunkedError::Resource)?;
+
+            let chunks = chunks
+                .into_iter()
+                .map(|chunk| chunk.ok_or(ChunkedError::MissingChunk))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let len = chunks.iter().map(|chunk| chunk.len() as u64).sum::<u64>();
+
+            if len != manifest.len {
+                return Err(ChunkedError::Length);
+            }
+
+            let mut buffer = Vec::with_capacity(len as usize);
+
+            for (hash, chunk) in manifest.chunks.into_iter().zip(chunks) {
+                let mut hasher = H::new();
+                hasher.write(&chunk);
+
+                if hasher.hash() != hash {
+                    return Err(ChunkedError::Mismatch);
+                }
+
+                buffer.extend_from_slice(&chunk);
+            }
+
+            U::rehydrate(buffer).await.map_err(ChunkedError::Inner)
+        })
+    }
+
+    fn dump(item: T) -> Self::Dump {
+        let item = U::dump(item);
+        let manager = acquire::<ErasedResourceManager>();
+
+        Box::pin(async move {
+            let item = item.await.map_err(ChunkedError::Inner)?;
+            let split = split::<A, H>(&item).map_err(ChunkedError::Manifest)?;
+
+            let manager = manager
+                .await
+                .map_err(ChunkedError::Core)?
+                .ok_or(ChunkedError::NoResourceManager)?;
+
+            let publishes = split
+                .chunks
+                .into_iter()
+                .map(|(hash, chunk)| {
+                    ResourceManager::publish(
+                        &manager,
+                        TypeId::of::<A>(),
+                        Box::new(move || Box::new(hash.clone()) as Box<dyn Any + Send>),
+                        chunk,
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            try_join_all(publishes)
+                .await
+                .map_err(ChunkedError::Resource)?;
+
+            Ok(split.manifest)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        register,
+        resource::{
+            manager::{ProviderMetadata, ResourceRegistrant, StoreRegistrant},
+            store::{ResourceStore, ResourceStoreExt},
+        },
+        test_util::{TestError, TestHasher},
+        with_core, Core, MemoryStore, Sha256, SimpleResourceManager,
+    };
+    use futures::executor::block_on;
+
+    type Blob = Chunked<Convert, Sha256, TestHasher>;
+
+    fn data(len: usize) -> Vec<u8> {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    fn manager(store: &MemoryStore<Sha256>) -> SimpleResourceManager {
+        let mut manager = SimpleResourceManager::new();
+
+        block_on(ResourceRegistrant::<Sha256, _>::register_provider(
+            &mut manager,
+            store.clone(),
+            ProviderMetadata::default(),
+        ))
+        .unwrap();
+
+        manager
+    }
+
+    #[test]
+    fn round_trip() {
+        let core = Core::new();
+        let store = MemoryStore::<Sha256>::new();
+        let manager = manager(&store);
+        let data = data(1024 * 1024);
+
+        with_core!(&core => {
+            block_on(async {
+                register(move || {
+                    let manager = manager.clone();
+                    async move { Ok::<_, TestError>(manager.into_erased()) }
+                })
+                .await
+                .unwrap();
+
+                let resource: Resource<Vec<u8>, Blob, Sha256> =
+                    store.intern_chunked::<TestHasher, _, _>(data.clone()).await.unwrap();
+
+                let manager = acquire::<ErasedResourceManager>().await.unwrap().unwrap();
+                let fetched = ResourceManagerExt::fetch(&manager, resource).await.unwrap();
+
+                assert!(fetched == Some(data));
+            })
+        });
+    }
+
+    #[test]
+    fn chunks_go_into_target_store() {
+        let store = MemoryStore::<Sha256>::new();
+        let data = data(512 * 1024);
+
+        let split = split::<Sha256, TestHasher>(&data).unwrap();
+        assert!(split.chunks.len() > 1);
+
+        let resource: Resource<Vec<u8>, Blob, Sha256> = block_on(
+            ResourceStoreExt::intern_chunked::<TestHasher, _, _>(&store, data),
+        )
+        .unwrap();
+
+        assert!(block_on(store.contains(resource.hash())).unwrap());
+        for (hash, _) in split.chunks {
+            assert!(block_on(store.contains(hash)).unwrap());
+        }
+    }
+
+    #[test]
+    fn publish_interns_chunks_through_manager() {
+        let core = Core::new();
+        let store = MemoryStore::<Sha256>::new();
+        let mut manager = manager(&store);
+        let data = data(512 * 1024);
+
+        block_on(StoreRegistrant::<Sha256, _>::register_store(
+            &mut manager,
+            store.clone(),
+            ProviderMetadata::default(),
+        ))
+        .unwrap();
+
+        with_core!(&core => {
+            block_on(async {
+                register({
+                    let manager = manager.clone();
+                    move || {
+                        let manager = manager.clone();
+                        async move { Ok::<_, TestError>(manager.into_erased()) }
+                    }
+                })
+                .await
+                .unwrap();
+
+                let resource: Resource<Vec<u8>, Blob, Sha256> =
+                    ResourceManagerExt::publish::<TestHasher, _, _, _>(&manager, data.clone())
+                        .await
+                        .unwrap();
+
+                for (hash, _) in split::<Sha256, TestHasher>(&data).unwrap().chunks {
+                    assert!(store.contains(hash).await.unwrap());
+                }
+
+                let fetched = ResourceManagerExt::fetch(&manager, resource).await.unwrap();
+
+                assert!(fetched == Some(data));
+            })
+        });
+    }
+
+    #[test]
+    fn dump_without_manager_fails() {
+        let core = Core::new();
+
+        with_core!(&core => {
+            let result = block_on(<Blob as Rehydrate<Vec<u8>>>::dump(vec![1, 2, 3]));
+
+            assert!(matches!(result, Err(ChunkedError::NoResourceManager)));
+        });
+    }
+
+    #[test]
+    fn manifest_length_is_checked() {
+        let core = Core::new();
+        let store = MemoryStore::<Sha256>::new();
+        let manager = manager(&store);
+        let data = data(128 * 1024);
+
+        let split = split::<Sha256, TestHasher>(&data).unwrap();
+        let mut manifest: Manifest<crate::Sha256Sum> = from_slice(&split.manifest).unwrap();
+        manifest.len = u64::max_value();
+        let manifest = to_vec(&manifest).unwrap();
+
+        for (hash, chunk) in split.chunks {
+            block_on(store.put_raw(hash, chunk)).unwrap();
+        }
+
+        with_core!(&core => {
+            block_on(async {
+                register(move || {
+                    let manager = manager.clone();
+                    async move { Ok::<_, TestError>(manager.into_erased()) }
+                })
+                .await
+                .unwrap();
+
+                let result = <Blob as Rehydrate<Vec<u8>>>::rehydrate(manifest).await;
+
+                assert!(matches!(result, Err(ChunkedError::Length)));
+            })
+        });
+    }
+}
@@ -0,0 +1,119 @@
+use super::hash::{Algorithm, Hasher};
+use core::marker::PhantomData;
+
+pub trait Domain {
+    const TAG: &'static str;
+
+    fn write_tag<A: Algorithm, H: Hasher<A>>(hasher: &mut H) {
+        hasher.write(&(Self::TAG.len() as u64).to_be_bytes());
+        hasher.write(Self::TAG.as_bytes());
+    }
+}
+
+impl Domain for Vec<u8> {
+    const TAG: &'static str = "bytes";
+}
+
+pub trait Reinterpret<T> {}
+
+impl<T> Reinterpret<T> for T {}
+
+pub struct Separated<H, T, U> {
+    hasher: H,
+    ty: PhantomData<fn() -> (T, U)>,
+}
+
+impl<A: Algorithm, H: Hasher<A>, T: Domain, U: Domain> Hasher<A> for Separated<H, T, U> {
+    fn new() -> Self {
+        let mut hasher = H::new();
+
+        T::write_tag(&mut hasher);
+        U::write_tag(&mut hasher);
+
+        Separated {
+            hasher,
+            ty: PhantomData,
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) {
+        self.hasher.write(data)
+    }
+
+    fn hash(&self) -> A::Hash {
+        self.hasher.hash()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        resource::{hash::HasherExt, store::ResourceStore},
+        runtime::{Module, Wasm},
+        test_util::TestHasher,
+        Cbor, Convert, MemoryStore, Resource, Sha256,
+    };
+    use futures::executor::block_on;
+
+    struct Alpha;
+
+    impl Domain for Alpha {
+        const TAG: &'static str = "alpha";
+    }
+
+    struct Beta;
+
+    impl Domain for Beta {
+        const TAG: &'static str = "beta";
+    }
+
+    #[test]
+    fn separated_hash_differs_from_plain() {
+        let data = b"payload".to_vec();
+
+        let plain = <TestHasher as HasherExt<Sha256>>::hash(data.clone());
+        let separated = <Separated<TestHasher, Vec<u8>, Convert> as HasherExt<Sha256>>::hash(data);
+
+        assert!(plain != separated);
+    }
+
+    #[test]
+    fn separated_hash_differs_across_domains() {
+        let data = b"payload".to_vec();
+
+        let cbor = <Separated<TestHasher, Vec<u8>, Cbor> as HasherExt<Sha256>>::hash(data.clone());
+        let convert = <Separated<TestHasher, Vec<u8>, Convert> as HasherExt<Sha256>>::hash(data);
+
+        assert!(cbor != convert);
+    }
+
+    #[test]
+    fn intern_separated_stores_under_separated_hash() {
+        let mut store = MemoryStore::<Sha256>::new();
+
+        let plain: Resource<Vec<u8>, Convert, Sha256> =
+            block_on(store.intern::<TestHasher, _, _>(vec![1, 2, 3])).unwrap();
+        let separated: Resource<Vec<u8>, Convert, Sha256> =
+            block_on(store.intern_separated::<TestHasher, _, _>(vec![1, 2, 3])).unwrap();
+
+        assert!(plain.hash() != separated.hash());
+        assert!(block_on(store.contains(plain.hash())).unwrap());
+        assert!(block_on(store.contains(separated.hash())).unwrap());
+    }
+
+    #[test]
+    fn module_hash_depends_on_interface() {
+        let data = b"\0asm".to_vec();
+
+        let alpha = <Separated<TestHasher, Module<Alpha>, Convert> as HasherExt<Sha256>>::hash(
+            data.clone(),
+        );
+        let beta =
+            <Separated<TestHasher, Module<Beta>, Convert> as HasherExt<Sha256>>::hash(data.clone());
+        let wasm = <Separated<TestHasher, Wasm, Convert> as HasherExt<Sha256>>::hash(data);
+
+        assert!(alpha != beta);
+        assert!(alpha != wasm);
+    }
+}
@@ -0,0 +1,277 @@
+use super::{
+    graph::{Link, Links},
+    hash::{Algorithm, Hasher},
+    Lazy, Rehydrate,
+};
+use crate::Resource;
+use core::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    fmt::Display,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use core_error::Error;
+use futures::{Future, FutureExt};
+use serde::{ser::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::sync::Mutex;
+
+trait Sink {
+    fn put(&mut self, algo: TypeId, data: Vec<u8>) -> Option<Box<dyn Any>>;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+struct Pending<A: Algorithm, H> {
+    entries: Vec<(A::Hash, Vec<u8>)>,
+    ty: PhantomData<fn() -> H>,
+}
+
+impl<A: Algorithm + Any, H: Hasher<A> + 'static> Sink for Pending<A, H>
+where
+    A::Hash: Clone + 'static,
+{
+    fn put(&mut self, algo: TypeId, data: Vec<u8>) -> Option<Box<dyn Any>> {
+        if algo != TypeId::of::<A>() {
+            return None;
+        }
+
+        let mut hasher = H::new();
+        hasher.write(&data);
+        let hash = hasher.hash();
+
+        self.entries.push((hash.clone(), data));
+
+        Some(Box::new(hash))
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+thread_local! {
+    static SINKS: RefCell<Vec<Box<dyn Sink>>> = RefCell::new(vec![]);
+}
+
+pub(crate) struct Collect<A: Algorithm, H, F> {
+    future: Pin<Box<F>>,
+    entries: Option<Vec<(A::Hash, Vec<u8>)>>,
+    ty: PhantomData<fn() -> H>,
+}
+
+pub(crate) fn collect<A: Algorithm, H, F: Future>(future: F) -> Collect<A, H, F> {
+    Collect {
+        future: Box::pin(future),
+        entries: Some(vec![]),
+        ty: PhantomData,
+    }
+}
+
+impl<A: Algorithm, H, F> Unpin for Collect<A, H, F> {}
+
+impl<A: Algorithm + Any, H: Hasher<A> + 'static, F: Future> Future for Collect<A, H, F>
+where
+    A::Hash: Clone + 'static,
+{
+    type Output = (F::Output, Vec<(A::Hash, Vec<u8>)>);
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+
+        SINKS.with(|sinks| {
+            sinks.borrow_mut().push(Box::new(Pending::<A, H> {
+                entries: this.entries.take().unwrap_or_default(),
+                ty: PhantomData,
+            }))
+        });
+
+        let poll = this.future.as_mut().poll(cx);
+
+        let entries = SINKS
+            .with(|sinks| sinks.borrow_mut().pop())
+            .and_then(|sink| sink.into_any().downcast::<Pending<A, H>>().ok())
+            .map(|pending| pending.entries)
+            .unwrap_or_default();
+
+        match poll {
+            Poll::Ready(output) => Poll::Ready((output, entries)),
+            Poll::Pending => {
+                this.entries = Some(entries);
+                Poll::Pending
+            }
+        }
+    }
+}
+
+enum State<T, U: Rehydrate<T>, A: Algorithm> {
+    Pending(Option<T>),
+    Stored(Lazy<T, U, A>),
+}
+
+pub struct Externalized<T, U: Rehydrate<T>, A: Algorithm>(Mutex<State<T, U, A>>);
+
+impl<T, U: Rehydrate<T>, A: Algorithm> Externalized<T, U, A> {
+    pub fn new(item: T) -> Self {
+        Externalized(Mutex::new(State::Pending(Some(item))))
+    }
+
+    pub fn lazy(&self) -> Option<Lazy<T, U, A>>
+    where
+        A::Hash: Clone,
+    {
+        match &*self.0.lock().unwrap() {
+            State::Stored(lazy) => Some(lazy.clone()),
+            State::Pending(_) => None,
+        }
+    }
+
+    pub fn into_lazy(self) -> Option<Lazy<T, U, A>> {
+        match self.0.into_inner().unwrap() {
+            State::Stored(lazy) => Some(lazy),
+            State::Pending(_) => None,
+        }
+    }
+}
+
+impl<T, U: Rehydrate<T>, A: Algorithm> From<Resource<T, U, A>> for Externalized<T, U, A> {
+    fn from(resource: Resource<T, U, A>) -> Self {
+        Externalized(Mutex::new(State::Stored(Lazy::new(resource))))
+    }
+}
+
+impl<T: Links<A>, U: Rehydrate<T>, A: Algorithm> Links<A> for Externalized<T, U, A>
+where
+    A::Hash: Clone,
+    U::Rehydrate: Send + 'static,
+    U::RehydrateError: Error + Send + 'static,
+{
+    fn links(&self) -> Vec<Link<A>> {
+        self.lazy()
+            .map(|lazy| vec![Link::new(lazy.resource())])
+            .unwrap_or_default()
+    }
+}
+
+impl<T, U: Rehydrate<T>, A: Algorithm + Any> Serialize for Externalized<T, U, A>
+where
+    A::Hash: Serialize + Clone + 'static,
+    U::DumpError: Display,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = self.0.lock().unwrap();
+
+        if let State::Pending(item) = &mut *state {
+            let item = item.take().ok_or_else(|| {
+                S::Error::custom("externalized value was consumed by a failed dump")
+            })?;
+
+            let data = U::dump(item)
+                .now_or_never()
+                .ok_or_else(|| S::Error::custom("externalized value did not dump synchronously"))?
+                .map_err(S::Error::custom)?;
+
+            let hash = SINKS
+                .with(|sinks| {
+                    sinks
+                        .borrow_mut()
+                        .last_mut()
+                        .and_then(|sink| sink.put(TypeId::of::<A>(), data))
+                })
+                .and_then(|hash| hash.downcast::<A::Hash>().ok())
+                .ok_or_else(|| {
+                    S::Error::custom("externalized value serialized outside of a publish")
+                })?;
+
+            *state = State::Stored(Lazy::new(Resource::new(*hash)));
+        }
+
+        match &*state {
+            State::Stored(lazy) => lazy.serialize(serializer),
+            State::Pending(_) => unreachable!(),
+        }
+    }
+}
+
+impl<'de, T, U: Rehydrate<T>, A: Algorithm> Deserialize<'de> for Externalized<T, U, A>
+where
+    A::Hash: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Lazy::deserialize(deserializer).map(|lazy| Externalized(Mutex::new(State::Stored(lazy))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        resource::{
+            hash::HasherExt,
+            manager::{ProviderMetadata, ResourceRegistrant, StoreRegistrant},
+            store::ResourceStore,
+            ResourceManagerExt,
+        },
+        test_util::TestHasher,
+        Cbor, Convert, MemoryStore, Sha256, SimpleResourceManager,
+    };
+    use futures::executor::block_on;
+
+    #[derive(Serialize, Deserialize)]
+    struct Document {
+        title: String,
+        body: Externalized<Vec<u8>, Convert, Sha256>,
+    }
+
+    fn document() -> Document {
+        Document {
+            title: "title".to_owned(),
+            body: Externalized::new(b"body".to_vec()),
+        }
+    }
+
+    #[test]
+    fn publish_interns_externalized_fields() {
+        let store = MemoryStore::<Sha256>::new();
+        let mut manager = SimpleResourceManager::new();
+
+        block_on(ResourceRegistrant::<Sha256, _>::register_provider(
+            &mut manager,
+            store.clone(),
+            ProviderMetadata::default(),
+        ))
+        .unwrap();
+        block_on(StoreRegistrant::<Sha256, _>::register_store(
+            &mut manager,
+            store.clone(),
+            ProviderMetadata::default(),
+        ))
+        .unwrap();
+
+        let resource: Resource<Document, Cbor, Sha256> =
+            block_on(ResourceManagerExt::publish::<TestHasher, _, _, _>(
+                &manager,
+                document(),
+            ))
+            .unwrap();
+        let body = <TestHasher as HasherExt<Sha256>>::hash(b"body".to_vec());
+
+        assert!(block_on(store.contains(body)).unwrap());
+
+        let document = block_on(ResourceManagerExt::fetch(&manager, resource))
+            .unwrap()
+            .unwrap();
+        let lazy = document.body.lazy().unwrap();
+
+        assert!(lazy.resource().hash() == body);
+        assert_eq!(
+            block_on(ResourceManagerExt::fetch(&manager, lazy.resource().clone())).unwrap(),
+            Some(b"body".to_vec())
+        );
+    }
+
+    #[test]
+    fn serializing_outside_a_publish_fails() {
+        assert!(serde_cbor::to_vec(&document()).is_err());
+    }
+}
@@ -0,0 +1,309 @@
+use super::{hash::Algorithm, manager::ResourceManager, Rehydrate, ResourceError};
+use crate::Resource;
+use core::{
+    any::{Any, TypeId},
+    convert::Infallible,
+    hash::Hash,
+};
+use core_error::Error;
+use futures::Future;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    pin::Pin,
+};
+use thiserror::Error;
+
+pub trait Links<A: Algorithm> {
+    fn links(&self) -> Vec<Link<A>>;
+}
+
+type Children<A> =
+    fn(
+        Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<Link<A>>, Box<dyn Error + Send>>> + Send>>;
+
+pub struct Link<A: Algorithm> {
+    hash: A::Hash,
+    children: Children<A>,
+}
+
+impl<A: Algorithm> Clone for Link<A>
+where
+    A::Hash: Clone,
+{
+    fn clone(&self) -> Self {
+        Link {
+            hash: self.hash.clone(),
+            children: self.children,
+        }
+    }
+}
+
+fn children<A: Algorithm, T: Links<A>, U: Rehydrate<T>>(
+    data: Vec<u8>,
+) -> Pin<Box<dyn Future<Output = Result<Vec<Link<A>>, Box<dyn Error + Send>>> + Send>>
+where
+    U::Rehydrate: Send + 'static,
+    U::RehydrateError: Error + Send + 'static,
+{
+    let item = U::rehydrate(data);
+
+    Box::pin(async move {
+        Ok(item
+            .await
+            .map_err(|e| Box::new(e) as Box<dyn Error + Send>)?
+            .links())
+    })
+}
+
+fn leaf<A: Algorithm>(
+    _: Vec<u8>,
+) -> Pin<Box<dyn Future<Output = Result<Vec<Link<A>>, Box<dyn Error + Send>>> + Send>> {
+    Box::pin(async { Ok(vec![]) })
+}
+
+impl<A: Algorithm> Link<A> {
+    pub fn new<T: Links<A>, U: Rehydrate<T>>(resource: &Resource<T, U, A>) -> Self
+    where
+        A::Hash: Clone,
+        U::Rehydrate: Send + 'static,
+        U::RehydrateError: Error + Send + 'static,
+    {
+        Link {
+            hash: resource.hash(),
+            children: children::<A, T, U>,
+        }
+    }
+
+    pub fn leaf(hash: A::Hash) -> Self {
+        Link {
+            hash,
+            children: leaf::<A>,
+        }
+    }
+
+    pub fn hash(&self) -> &A::Hash {
+        &self.hash
+    }
+}
+
+impl<A: Algorithm, T: Links<A>, U: Rehydrate<T>> Links<A> for Resource<T, U, A>
+where
+    A::Hash: Clone,
+    U::Rehydrate: Send + 'static,
+    U::RehydrateError: Error + Send + 'static,
+{
+    fn links(&self) -> Vec<Link<A>> {
+        vec![Link::new(self)]
+    }
+}
+
+impl<A: Algorithm, T: Links<A>> Links<A> for Vec<T> {
+    fn links(&self) -> Vec<Link<A>> {
+        self.iter().flat_map(Links::links).collect()
+    }
+}
+
+impl<A: Algorithm, T: Links<A>> Links<A> for Option<T> {
+    fn links(&self) -> Vec<Link<A>> {
+        self.iter().flat_map(Links::links).collect()
+    }
+}
+
+impl<A: Algorithm, T: Links<A> + ?Sized> Links<A> for Box<T> {
+    fn links(&self) -> Vec<Link<A>> {
+        T::links(self)
+    }
+}
+
+impl<A: Algorithm, K, V: Links<A>, S> Links<A> for HashMap<K, V, S> {
+    fn links(&self) -> Vec<Link<A>> {
+        self.values().flat_map(Links::links).collect()
+    }
+}
+
+impl<A: Algorithm, K, V: Links<A>> Links<A> for BTreeMap<K, V> {
+    fn links(&self) -> Vec<Link<A>> {
+        self.values().flat_map(Links::links).collect()
+    }
+}
+
+impl<A: Algorithm> Links<A> for () {
+    fn links(&self) -> Vec<Link<A>> {
+        vec![]
+    }
+}
+
+macro_rules! tuple_links {
+    ($($ty:ident),+) => {
+        impl<A: Algorithm, $($ty: Links<A>),+> Links<A> for ($($ty,)+) {
+            #[allow(non_snake_case)]
+            fn links(&self) -> Vec<Link<A>> {
+                let ($($ty,)+) = self;
+                let mut links = vec![];
+                $(links.extend($ty.links());)+
+                links
+            }
+        }
+    };
+}
+
+tuple_links!(T);
+tuple_links!(T, U);
+tuple_links!(T, U, V);
+tuple_links!(T, U, V, W);
+
+impl<A: Algorithm> Links<A> for u8 {
+    fn links(&self) -> Vec<Link<A>> {
+        vec![]
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ClosureError {
+    #[error("resource error: {0}")]
+    Resource(#[source] ResourceError<Infallible>),
+    #[error("links error: {0}")]
+    Links(#[source] Box<dyn Error + Send>),
+}
+
+pub struct Closure<A: Algorithm> {
+    pub resources: HashMap<A::Hash, Vec<u8>>,
+    pub missing: HashSet<A::Hash>,
+}
+
+impl<A: Algorithm> Closure<A>
+where
+    A::Hash: Eq + Hash + Clone,
+{
+    pub fn reachable(&self) -> HashSet<A::Hash> {
+        self.resources
+            .keys()
+            .chain(self.missing.iter())
+            .cloned()
+            .collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+pub(crate) fn visit<M: ResourceManager + ?Sized, A: Algorithm + Any>(
+    manager: &M,
+    link: Link<A>,
+) -> impl Future<Output = Result<(A::Hash, Option<Vec<u8>>, Vec<Link<A>>), ClosureError>>
+where
+    A::Hash: Clone + Send + 'static,
+{
+    let hash = link.hash.clone();
+    let fetch = manager.fetch(TypeId::of::<A>(), Box::new(move || Box::new(hash.clone())));
+
+    async move {
+        let data = fetch.await.map_err(ClosureError::Resource)?;
+
+        let children = if let Some(data) = &data {
+            (link.children)(data.clone())
+                .await
+                .map_err(ClosureError::Links)?
+        } else {
+            vec![]
+        };
+
+        Ok((link.hash, data, children))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        resource::{
+            manager::{ProviderMetadata, ResourceRegistrant},
+            ResourceManagerExt,
+        },
+        test_util::TestHasher,
+        Cbor, MemoryStore, Sha256, Sha256Sum, SimpleResourceManager,
+    };
+    use futures::executor::block_on;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Node {
+        name: String,
+        children: Vec<Resource<Node, Cbor, Sha256>>,
+    }
+
+    impl Links<Sha256> for Node {
+        fn links(&self) -> Vec<Link<Sha256>> {
+            self.children.links()
+        }
+    }
+
+    fn node(
+        store: &mut MemoryStore<Sha256>,
+        name: &str,
+        children: Vec<Resource<Node, Cbor, Sha256>>,
+    ) -> Resource<Node, Cbor, Sha256> {
+        block_on(store.intern::<TestHasher, _, Cbor>(Node {
+            name: name.to_owned(),
+            children,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn closure_walks_shared_and_missing_nodes() {
+        let mut store = MemoryStore::<Sha256>::new();
+
+        let shared = node(&mut store, "shared", vec![]);
+        let left = node(&mut store, "left", vec![shared.clone()]);
+        let right = node(&mut store, "right", vec![shared.clone()]);
+        let missing = block_on(Resource::<Node, Cbor, Sha256>::compute::<TestHasher>(
+            Node {
+                name: "missing".to_owned(),
+                children: vec![],
+            },
+        ))
+        .unwrap();
+        let root = node(&mut store, "root", vec![left, right, missing.clone()]);
+
+        let mut manager = SimpleResourceManager::new();
+        block_on(ResourceRegistrant::<Sha256, _>::register_provider(
+            &mut manager,
+            store,
+            ProviderMetadata::default(),
+        ))
+        .unwrap();
+
+        let closure = block_on(manager.fetch_closure(vec![Link::new(&root)])).unwrap();
+
+        assert_eq!(closure.resources.len(), 4);
+        assert!(closure.resources.contains_key(&shared.hash()));
+        assert!(closure.missing.contains(&missing.hash()));
+        assert!(!closure.is_complete());
+
+        let reachable = block_on(manager.reachable(vec![Link::new(&root)])).unwrap();
+
+        assert_eq!(reachable.len(), 5);
+    }
+
+    #[test]
+    fn containers_collect_links() {
+        let (first, second) = (Sha256Sum([1; 32]), Sha256Sum([2; 32]));
+        let first = Resource::<Vec<u8>, Cbor, Sha256>::new(first);
+        let second = Resource::<Vec<u8>, Cbor, Sha256>::new(second);
+
+        let mut map = BTreeMap::new();
+        map.insert("first", first.clone());
+
+        let value = (Box::new(second.clone()), map, Some(first.clone()));
+        let links = value
+            .links()
+            .into_iter()
+            .map(|link| link.hash().clone())
+            .collect::<Vec<_>>();
+
+        assert!(links == vec![second.hash(), first.hash(), first.hash()]);
+    }
+}
@@ -33,3 +33,28 @@ pub trait HasherExt<A: Algorithm>: Hasher<A> {
 }
 
 impl<A: Algorithm, T: Hasher<A>> HasherExt<A> for T {}
+
+pub trait Digest: Sized {
+    fn as_bytes(&self) -> &[u8];
+    fn from_bytes(data: &[u8]) -> Option<Self>;
+
+    fn to_hex(&self) -> String {
+        self.as_bytes()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    fn from_hex(data: &str) -> Option<Self> {
+        if data.len() % 2 != 0 || !data.is_ascii() {
+            return None;
+        }
+
+        let bytes = (0..data.len())
+            .step_by(2)
+            .map(|idx| u8::from_str_radix(&data[idx..idx + 2], 16).ok())
+            .collect::<Option<Vec<_>>>()?;
+
+        Self::from_bytes(&bytes)
+    }
+}
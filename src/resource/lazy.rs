@@ -0,0 +1,209 @@
+use super::{hash::Algorithm, ErasedResourceManager, Rehydrate, ResourceError, ResourceManagerExt};
+use crate::{acquire, CoreError, Resource};
+use core::any::Any;
+use core_error::Error;
+use futures::{lock::Mutex, Future};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[bounds(where T: Error + 'static)]
+pub enum LazyError<T> {
+    #[error("no active resource manager")]
+    NoResourceManager,
+    #[error("resource not found")]
+    NotFound,
+    #[error("core error: {0}")]
+    Core(#[source] CoreError),
+    #[error("resource error: {0}")]
+    Resource(#[source] ResourceError<T>),
+}
+
+pub struct Lazy<T, U: Rehydrate<T>, A: Algorithm> {
+    resource: Resource<T, U, A>,
+    item: Arc<Mutex<Option<Arc<T>>>>,
+}
+
+impl<T, U: Rehydrate<T>, A: Algorithm> Clone for Lazy<T, U, A>
+where
+    A::Hash: Clone,
+{
+    fn clone(&self) -> Self {
+        Lazy {
+            resource: self.resource.clone(),
+            item: self.item.clone(),
+        }
+    }
+}
+
+impl<T, U: Rehydrate<T>, A: Algorithm> From<Resource<T, U, A>> for Lazy<T, U, A> {
+    fn from(resource: Resource<T, U, A>) -> Self {
+        Lazy::new(resource)
+    }
+}
+
+impl<T, U: Rehydrate<T>, A: Algorithm> Lazy<T, U, A> {
+    pub fn new(resource: Resource<T, U, A>) -> Self {
+        Lazy {
+            resource,
+            item: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn resource(&self) -> &Resource<T, U, A> {
+        &self.resource
+    }
+
+    pub fn get(&self) -> impl Future<Output = Result<Arc<T>, LazyError<U::RehydrateError>>>
+    where
+        A: Any,
+        A::Hash: Clone + Send,
+        T: Send + 'static,
+        U: Send + 'static,
+    {
+        let manager = acquire::<ErasedResourceManager>();
+        let resource = self.resource.clone();
+        let item = self.item.clone();
+
+        async move {
+            let mut item = item.lock().await;
+
+            if let Some(item) = &*item {
+                return Ok(item.clone());
+            }
+
+            let manager = manager
+                .await
+                .map_err(LazyError::Core)?
+                .ok_or(LazyError::NoResourceManager)?;
+
+            let fetched = Arc::new(
+                ResourceManagerExt::fetch(&manager, resource)
+                    .await
+                    .map_err(LazyError::Resource)?
+                    .ok_or(LazyError::NotFound)?,
+            );
+
+            *item = Some(fetched.clone());
+
+            Ok(fetched)
+        }
+    }
+}
+
+impl<T, U: Rehydrate<T>, A: Algorithm> Serialize for Lazy<T, U, A>
+where
+    A::Hash: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.resource.serialize(serializer)
+    }
+}
+
+impl<'de, T, U: Rehydrate<T>, A: Algorithm> Deserialize<'de> for Lazy<T, U, A>
+where
+    A::Hash: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Resource::deserialize(deserializer).map(Lazy::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        register,
+        resource::{
+            manager::{ProviderMetadata, ResourceRegistrant},
+            provider::ResourceProvider,
+            store::ResourceStoreExt,
+        },
+        test_util::{TestError, TestHasher},
+        with_core, Convert, Core, MemoryStore, Sha256, Sha256Sum, SimpleResourceManager,
+    };
+    use futures::executor::block_on;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Clone)]
+    struct Counting {
+        store: MemoryStore<Sha256>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl ResourceProvider<Sha256> for Counting {
+        type Fetch = <MemoryStore<Sha256> as ResourceProvider<Sha256>>::Fetch;
+
+        fn fetch(&self, hash: Sha256Sum) -> Self::Fetch {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.store.fetch(hash)
+        }
+    }
+
+    fn with_manager<F: Future<Output = ()>>(counting: Counting, test: F) {
+        let core = Core::new();
+        let mut manager = SimpleResourceManager::new();
+
+        block_on(ResourceRegistrant::<Sha256, _>::register_provider(
+            &mut manager,
+            counting,
+            ProviderMetadata::default(),
+        ))
+        .unwrap();
+
+        with_core!(&core => {
+            block_on(async {
+                register(move || {
+                    let manager = manager.clone();
+                    async move { Ok::<_, TestError>(manager.into_erased()) }
+                })
+                .await
+                .unwrap();
+
+                test.await;
+            })
+        });
+    }
+
+    #[test]
+    fn fetches_once_and_caches() {
+        let store = MemoryStore::<Sha256>::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let resource: Resource<Vec<u8>, Convert, Sha256> =
+            block_on(ResourceStoreExt::intern::<TestHasher, _, _>(
+                &store,
+                b"data".to_vec(),
+            ))
+            .unwrap();
+        let lazy = Lazy::new(resource);
+        let counting = Counting {
+            store,
+            calls: calls.clone(),
+        };
+
+        with_manager(counting, async {
+            let first = lazy.get().await.unwrap();
+            let second = lazy.clone().get().await.unwrap();
+
+            assert_eq!(*first, b"data".to_vec());
+            assert!(Arc::ptr_eq(&first, &second));
+            assert_eq!(calls.load(Ordering::SeqCst), 1);
+        });
+    }
+
+    #[test]
+    fn missing_resource_is_not_found() {
+        let counting = Counting {
+            store: MemoryStore::new(),
+            calls: Arc::new(AtomicUsize::new(0)),
+        };
+        let lazy = Lazy::new(Resource::<Vec<u8>, Convert, Sha256>::new(Sha256Sum(
+            [0; 32],
+        )));
+
+        with_manager(counting, async {
+            assert!(matches!(lazy.get().await, Err(LazyError::NotFound)));
+        });
+    }
+}
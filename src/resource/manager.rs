@@ -1,19 +1,29 @@
-use super::{hash::Algorithm, Rehydrate};
+use super::{
+    externalized::collect,
+    hash::{Algorithm, Hasher},
+    Rehydrate,
+};
 use crate::{
     resource::{
+        graph::{visit, Closure, ClosureError, Link},
         provider::{ErrorErasedResourceProvider, ResourceProvider},
+        store::{ErrorErasedResourceStore, ResourceStore},
         ResourceError,
     },
     Resource,
 };
 use futures::{
     future::{ready, AndThen, Either, MapErr, MapOk, Ready},
-    Future, TryFuture, TryFutureExt,
+    stream::FuturesUnordered,
+    Future, StreamExt, TryFuture, TryFutureExt,
 };
 use protocol::protocol;
+use serde::{Deserialize, Serialize};
 use std::{
     any::{Any, TypeId},
+    collections::{HashMap, HashSet},
     convert::Infallible,
+    hash::Hash,
     pin::Pin,
 };
 
@@ -25,6 +35,15 @@ pub trait ResourceManager {
         algo: TypeId,
         hash: Box<dyn FnMut() -> Box<dyn Any + Send> + Send>,
     ) -> Self::Fetch;
+
+    fn publish(
+        &self,
+        _: TypeId,
+        _: Box<dyn FnMut() -> Box<dyn Any + Send> + Send>,
+        _: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), ResourceError<Infallible>>> + Send>> {
+        Box::pin(ready(Err(ResourceError::NoStore)))
+    }
 }
 
 impl<T: ?Sized + ResourceManager> ResourceManager for Box<T> {
@@ -37,6 +56,15 @@ impl<T: ?Sized + ResourceManager> ResourceManager for Box<T> {
     ) -> Self::Fetch {
         T::fetch(self, algo, hash)
     }
+
+    fn publish(
+        &self,
+        algo: TypeId,
+        hash: Box<dyn FnMut() -> Box<dyn Any + Send> + Send>,
+        data: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), ResourceError<Infallible>>> + Send>> {
+        T::publish(self, algo, hash, data)
+    }
 }
 
 pub type ErasedResourceManager = Box<
@@ -65,6 +93,15 @@ where
     ) -> Self::Fetch {
         Box::pin(self.manager.fetch(algo, hash))
     }
+
+    fn publish(
+        &self,
+        algo: TypeId,
+        hash: Box<dyn FnMut() -> Box<dyn Any + Send> + Send>,
+        data: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), ResourceError<Infallible>>> + Send>> {
+        self.manager.publish(algo, hash, data)
+    }
 }
 
 pub trait ResourceManagerExt: ResourceManager {
@@ -138,28 +175,206 @@ pub trait ResourceManagerExt: ResourceManager {
                 >,
         )
     }
+
+    fn publish<'a, H: Hasher<A> + 'static, A: Algorithm + Any, T, U: Rehydrate<T>>(
+        &'a self,
+        item: T,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Resource<T, U, A>, ResourceError<U::DumpError>>> + Send + 'a,
+        >,
+    >
+    where
+        Self: Sync,
+        A::Hash: Clone + Send + 'static,
+        U::Dump: Send + 'a,
+        U::DumpError: Send + 'static,
+    {
+        let item = collect::<A, H, _>(U::dump(item));
+
+        Box::pin(async move {
+            let (item, externalized) = item.await;
+            let item = item.map_err(ResourceError::Rehydration)?;
+
+            for (hash, data) in externalized {
+                ResourceManager::publish(
+                    self,
+                    TypeId::of::<A>(),
+                    Box::new(move || Box::new(hash.clone())),
+                    data,
+                )
+                .await
+                .map_err(ResourceError::cast)?;
+            }
+
+            let mut hasher = H::new();
+            hasher.write(&item);
+            let hash = hasher.hash();
+
+            let publish = ResourceManager::publish(
+                self,
+                TypeId::of::<A>(),
+                Box::new({
+                    let hash = hash.clone();
+                    move || Box::new(hash.clone())
+                }),
+                item,
+            );
+
+            publish.await.map_err(ResourceError::cast)?;
+
+            Ok(Resource::new(hash))
+        })
+    }
+
+    fn fetch_closure<'a, A: Algorithm + Any>(
+        &'a self,
+        roots: Vec<Link<A>>,
+    ) -> Pin<Box<dyn Future<Output = Result<Closure<A>, ClosureError>> + Send + 'a>>
+    where
+        Self: Sync,
+        Self::Fetch: Send + 'static,
+        A::Hash: Eq + Hash + Clone + Send + 'static,
+    {
+        Box::pin(async move {
+            let mut seen = HashSet::new();
+            let mut pending = FuturesUnordered::new();
+            let mut closure = Closure {
+                resources: HashMap::new(),
+                missing: HashSet::new(),
+            };
+
+            for link in roots {
+                if seen.insert(link.hash().clone()) {
+                    pending.push(visit(self, link));
+                }
+            }
+
+            while let Some(item) = pending.next().await {
+                let (hash, data, children) = item?;
+
+                if let Some(data) = data {
+                    closure.resources.insert(hash, data);
+
+                    for link in children {
+                        if seen.insert(link.hash().clone()) {
+                            pending.push(visit(self, link));
+                        }
+                    }
+                } else {
+                    closure.missing.insert(hash);
+                }
+            }
+
+            Ok(closure)
+        })
+    }
+
+    fn reachable<'a, A: Algorithm + Any>(
+        &'a self,
+        roots: Vec<Link<A>>,
+    ) -> Pin<Box<dyn Future<Output = Result<HashSet<A::Hash>, ClosureError>> + Send + 'a>>
+    where
+        Self: Sync,
+        Self::Fetch: Send + 'static,
+        A::Hash: Eq + Hash + Clone + Send + 'static,
+    {
+        Box::pin(
+            self.fetch_closure(roots)
+                .map_ok(|closure| closure.reachable()),
+        )
+    }
 }
 
 impl<T: ResourceManager> ResourceManagerExt for T {}
 
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProviderMetadata {
+    pub name: Option<String>,
+    pub priority: i32,
+    pub read_only: bool,
+}
+
+impl ProviderMetadata {
+    pub fn named<T: Into<String>>(name: T) -> Self {
+        ProviderMetadata {
+            name: Some(name.into()),
+            ..ProviderMetadata::default()
+        }
+    }
+
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+}
+
+#[protocol]
+pub trait ProviderHandle {
+    type Unregister: TryFuture<Ok = ()>;
+    type Reprioritize: TryFuture<Ok = ()>;
+
+    fn unregister(&mut self) -> Self::Unregister;
+
+    fn reprioritize(&mut self, priority: i32) -> Self::Reprioritize;
+}
+
+pub type ErasedProviderHandle<E> = Box<
+    dyn ProviderHandle<
+            Unregister = Pin<Box<dyn Future<Output = Result<(), E>> + Send>>,
+            Reprioritize = Pin<Box<dyn Future<Output = Result<(), E>> + Send>>,
+        > + Send,
+>;
+
 #[protocol]
 pub trait ResourceRegistrant<A, T>
 where
     A: Algorithm,
     T: ResourceProvider<A>,
 {
-    type Register: TryFuture<Ok = ()>;
+    type Handle: ProviderHandle;
+    type Register: TryFuture<Ok = Self::Handle>;
 
-    fn register_provider(&mut self, provider: T) -> Self::Register;
+    fn register_provider(&mut self, provider: T, metadata: ProviderMetadata) -> Self::Register;
 }
 
 pub type ErasedResourceRegistrant<A, E> = Box<
     dyn ResourceRegistrant<
             A,
             ErrorErasedResourceProvider<A>,
-            Register = Pin<Box<dyn Future<Output = Result<(), E>> + Send>>,
+            Handle = ErasedProviderHandle<E>,
+            Register = Pin<Box<dyn Future<Output = Result<ErasedProviderHandle<E>, E>> + Send>>,
         > + Send,
 >;
 
 pub type ErrorErasedResourceRegistrant<A> =
     ErasedResourceRegistrant<A, Box<dyn core_error::Error + Send>>;
+
+#[protocol]
+pub trait StoreRegistrant<A, T>
+where
+    A: Algorithm,
+    T: ResourceStore<A>,
+{
+    type Handle: ProviderHandle;
+    type Register: TryFuture<Ok = Self::Handle>;
+
+    fn register_store(&mut self, store: T, metadata: ProviderMetadata) -> Self::Register;
+}
+
+pub type ErasedStoreRegistrant<A, E> = Box<
+    dyn StoreRegistrant<
+            A,
+            ErrorErasedResourceStore<A>,
+            Handle = ErasedProviderHandle<E>,
+            Register = Pin<Box<dyn Future<Output = Result<ErasedProviderHandle<E>, E>> + Send>>,
+        > + Send,
+>;
+
+pub type ErrorErasedStoreRegistrant<A> =
+    ErasedStoreRegistrant<A, Box<dyn core_error::Error + Send>>;
@@ -1,13 +1,30 @@
-use core::{convert::Infallible, marker::PhantomData};
+use core::{
+    convert::Infallible,
+    fmt::{self, Display, Formatter},
+    future::Future,
+    marker::PhantomData,
+};
 use core_error::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use thiserror::Error;
 
 mod rehydrate;
 pub use rehydrate::Rehydrate;
+#[cfg(feature = "chunked")]
+pub mod chunked;
+pub mod domain;
+use domain::Reinterpret;
+mod externalized;
+pub use externalized::Externalized;
+pub mod graph;
 pub mod hash;
-use hash::Algorithm;
+use hash::{Algorithm, Hasher};
+mod lazy;
+pub use lazy::{Lazy, LazyError};
 pub mod manager;
 pub mod provider;
+pub mod store;
+pub mod stream;
 pub use manager::{ErasedResourceManager, ResourceManagerExt};
 
 pub struct Resource<T, U: Rehydrate<T>, A: Algorithm>(A::Hash, PhantomData<(T, U)>);
@@ -32,6 +49,59 @@ impl<T, U: Rehydrate<T>, A: Algorithm> Resource<T, U, A> {
     {
         self.0.clone()
     }
+
+    pub fn reinterpret<V>(self) -> Resource<V, U, A>
+    where
+        T: Reinterpret<V>,
+        U: Rehydrate<V>,
+    {
+        Resource(self.0, PhantomData)
+    }
+
+    pub fn cast_unchecked<V, W: Rehydrate<V>>(self) -> Resource<V, W, A> {
+        Resource(self.0, PhantomData)
+    }
+
+    pub fn compute<H: Hasher<A>>(item: T) -> impl Future<Output = Result<Self, U::DumpError>> {
+        let item = U::dump(item);
+
+        async move {
+            let mut hasher = H::new();
+
+            hasher.write(&item.await?);
+
+            Ok(Resource::new(hasher.hash()))
+        }
+    }
+
+    pub fn verify<H: Hasher<A>>(&self, data: &[u8]) -> bool
+    where
+        A::Hash: PartialEq,
+    {
+        let mut hasher = H::new();
+
+        hasher.write(data);
+
+        hasher.hash() == self.0
+    }
+}
+
+impl<T, U: Rehydrate<T>, A: Algorithm> Serialize for Resource<T, U, A>
+where
+    A::Hash: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, T, U: Rehydrate<T>, A: Algorithm> Deserialize<'de> for Resource<T, U, A>
+where
+    A::Hash: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        A::Hash::deserialize(deserializer).map(Resource::new)
+    }
 }
 
 #[derive(Debug, Error)]
@@ -43,14 +113,49 @@ pub enum ResourceError<T> {
     UnknownAlgorithm,
     #[error("rehydration error: {0}")]
     Rehydration(#[source] T),
+    #[error("error from store: {0}")]
+    Store(#[source] Box<dyn Error + Send>),
+    #[error("no writable store")]
+    NoStore,
+    #[error("all providers failed: {0}")]
+    Providers(ProviderErrors),
+}
+
+#[derive(Debug)]
+pub struct ProviderFailure {
+    pub index: usize,
+    pub name: Option<String>,
+    pub error: Box<dyn Error + Send>,
+}
+
+#[derive(Debug)]
+pub struct ProviderErrors(pub Vec<ProviderFailure>);
+
+impl Display for ProviderErrors {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (idx, failure) in self.0.iter().enumerate() {
+            if idx != 0 {
+                write!(f, "; ")?;
+            }
+            match &failure.name {
+                Some(name) => write!(f, "[{}] {}", name, failure.error)?,
+                None => write!(f, "[{}] {}", failure.index, failure.error)?,
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl ResourceError<Infallible> {
     fn cast<E>(self) -> ResourceError<E> {
         match self {
             ResourceError::Provider(e) => ResourceError::Provider(e),
+            ResourceError::Store(e) => ResourceError::Store(e),
+            ResourceError::Providers(e) => ResourceError::Providers(e),
             ResourceError::Rehydration(_) => panic!(),
             ResourceError::UnknownAlgorithm => ResourceError::UnknownAlgorithm,
+            ResourceError::NoStore => ResourceError::NoStore,
         }
     }
 }
@@ -60,3 +165,54 @@ impl<T> From<Box<dyn Error + Send>> for ResourceError<T> {
         ResourceError::Provider(input)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        resource::manager::{ProviderMetadata, StoreRegistrant},
+        test_util::TestHasher,
+        Cbor, MemoryStore, Sha256, SimpleResourceManager,
+    };
+    use futures::executor::block_on;
+
+    #[test]
+    fn compute_matches_publish() {
+        let mut manager = SimpleResourceManager::new();
+        block_on(StoreRegistrant::<Sha256, _>::register_store(
+            &mut manager,
+            MemoryStore::new(),
+            ProviderMetadata::default(),
+        ))
+        .unwrap();
+
+        let computed = block_on(Resource::<String, Cbor, Sha256>::compute::<TestHasher>(
+            "value".to_owned(),
+        ))
+        .unwrap();
+        let published: Resource<String, Cbor, Sha256> =
+            block_on(ResourceManagerExt::publish::<TestHasher, _, _, _>(
+                &manager,
+                "value".to_owned(),
+            ))
+            .unwrap();
+
+        assert!(computed.hash() == published.hash());
+    }
+
+    #[test]
+    fn verify_rejects_tampered_bytes() {
+        let data = serde_cbor::to_vec(&"value").unwrap();
+        let resource = block_on(Resource::<String, Cbor, Sha256>::compute::<TestHasher>(
+            "value".to_owned(),
+        ))
+        .unwrap();
+
+        assert!(resource.verify::<TestHasher>(&data));
+
+        let mut tampered = data;
+        tampered[1] ^= 1;
+
+        assert!(!resource.verify::<TestHasher>(&tampered));
+    }
+}
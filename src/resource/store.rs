@@ -0,0 +1,261 @@
+#[cfg(feature = "chunked")]
+use super::chunked::{split, Chunked};
+use super::{
+    hash::{Algorithm, Hasher},
+    manager::{ResourceManager, ResourceManagerExt},
+    Rehydrate,
+};
+use crate::Resource;
+use core_error::Error;
+#[cfg(feature = "chunked")]
+use futures::future::try_join_all;
+use futures::{Future, TryFuture, TryFutureExt};
+use protocol::protocol;
+#[cfg(feature = "chunked")]
+use serde::{de::DeserializeOwned, Serialize};
+#[cfg(feature = "chunked")]
+use std::hash::Hash;
+use std::{any::Any, marker::PhantomData, pin::Pin};
+
+#[protocol]
+pub trait ResourceStore<A: Algorithm> {
+    type Put: TryFuture<Ok = ()>;
+    type Remove: TryFuture<Ok = bool>;
+    type Contains: TryFuture<Ok = bool>;
+
+    fn put_raw(&self, hash: <A as Algorithm>::Hash, data: Vec<u8>) -> Self::Put;
+    fn remove(&self, hash: <A as Algorithm>::Hash) -> Self::Remove;
+    fn contains(&self, hash: <A as Algorithm>::Hash) -> Self::Contains;
+}
+
+struct ResourceStoreEraser<A: Algorithm, T: ResourceStore<A>> {
+    store: T,
+    algo: PhantomData<A>,
+}
+
+impl<A: Algorithm, T: ResourceStore<A>> ResourceStore<A> for ResourceStoreEraser<A, T>
+where
+    T::Put: Send + 'static,
+    T::Remove: Send + 'static,
+    T::Contains: Send + 'static,
+    <T::Put as TryFuture>::Error: Error + Send + 'static,
+    <T::Remove as TryFuture>::Error: Error + Send + 'static,
+    <T::Contains as TryFuture>::Error: Error + Send + 'static,
+{
+    type Put = Pin<Box<dyn Future<Output = Result<(), Box<dyn Error + Send>>> + Send>>;
+    type Remove = Pin<Box<dyn Future<Output = Result<bool, Box<dyn Error + Send>>> + Send>>;
+    type Contains = Pin<Box<dyn Future<Output = Result<bool, Box<dyn Error + Send>>> + Send>>;
+
+    fn put_raw(&self, hash: A::Hash, data: Vec<u8>) -> Self::Put {
+        Box::pin(
+            self.store
+                .put_raw(hash, data)
+                .map_err(|e| Box::new(e) as Box<dyn Error + Send>),
+        )
+    }
+
+    fn remove(&self, hash: A::Hash) -> Self::Remove {
+        Box::pin(
+            self.store
+                .remove(hash)
+                .map_err(|e| Box::new(e) as Box<dyn Error + Send>),
+        )
+    }
+
+    fn contains(&self, hash: A::Hash) -> Self::Contains {
+        Box::pin(
+            self.store
+                .contains(hash)
+                .map_err(|e| Box::new(e) as Box<dyn Error + Send>),
+        )
+    }
+}
+
+pub type ErrorErasedResourceStore<A> = Box<
+    dyn ResourceStore<
+            A,
+            Put = Pin<Box<dyn Future<Output = Result<(), Box<dyn Error + Send>>> + Send>>,
+            Remove = Pin<Box<dyn Future<Output = Result<bool, Box<dyn Error + Send>>> + Send>>,
+            Contains = Pin<Box<dyn Future<Output = Result<bool, Box<dyn Error + Send>>> + Send>>,
+        > + Send
+        + Sync,
+>;
+
+pub trait ResourceStoreExt<A: Algorithm>: ResourceStore<A> {
+    fn erase_store(self) -> ErrorErasedResourceStore<A>
+    where
+        Self: Sized + Send + Sync + 'static,
+        A: Send + Sync + 'static,
+        Self::Put: Send + 'static,
+        Self::Remove: Send + 'static,
+        Self::Contains: Send + 'static,
+        <Self::Put as TryFuture>::Error: Error + Send + 'static,
+        <Self::Remove as TryFuture>::Error: Error + Send + 'static,
+        <Self::Contains as TryFuture>::Error: Error + Send + 'static,
+    {
+        Box::new(ResourceStoreEraser {
+            store: self,
+            algo: PhantomData,
+        })
+    }
+
+    fn put<H: Hasher<A>>(
+        &self,
+        data: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = Result<A::Hash, <Self::Put as TryFuture>::Error>> + Send>>
+    where
+        A::Hash: Clone + Send + 'static,
+        Self::Put: Send + 'static,
+    {
+        let mut hasher = H::new();
+
+        hasher.write(&data);
+
+        let hash = hasher.hash();
+
+        Box::pin(
+            self.put_raw(hash.clone(), data)
+                .into_future()
+                .map_ok(move |()| hash),
+        )
+    }
+
+    fn intern<'a, H: Hasher<A>, T, U: Rehydrate<T>>(
+        &'a self,
+        item: T,
+    ) -> Pin<Box<dyn Future<Output = Result<Resource<T, U, A>, Box<dyn Error + Send>>> + Send + 'a>>
+    where
+        Self: Sync,
+        A::Hash: Clone + Send + 'static,
+        U::Dump: Send + 'a,
+        U::DumpError: Error + Send + 'static,
+        Self::Put: Send + 'static,
+        <Self::Put as TryFuture>::Error: Error + Send + 'static,
+    {
+        let item = U::dump(item);
+
+        Box::pin(async move {
+            let item = item
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn Error + Send>)?;
+
+            let hash = self
+                .put::<H>(item)
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn Error + Send>)?;
+
+            Ok(Resource::new(hash))
+        })
+    }
+
+    fn transcode<'a, H: Hasher<A>, T, U: Rehydrate<T>, V: Rehydrate<T>, M: ResourceManager>(
+        &'a self,
+        manager: &M,
+        resource: Resource<T, U, A>,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Option<Resource<T, V, A>>, Box<dyn Error + Send>>>
+                + Send
+                + 'a,
+        >,
+    >
+    where
+        Self: Sync,
+        A: Any,
+        A::Hash: Clone + Send + 'static,
+        T: Send + 'static,
+        U: Send + 'static,
+        U::Rehydrate: Send + 'static,
+        U::RehydrateError: Error + Send + 'static,
+        V::Dump: Send + 'a,
+        V::DumpError: Error + Send + 'static,
+        M::Fetch: Send + 'static,
+        Self::Put: Send + 'static,
+        <Self::Put as TryFuture>::Error: Error + Send + 'static,
+    {
+        let fetch = ResourceManagerExt::fetch(manager, resource);
+
+        Box::pin(async move {
+            let item = fetch
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn Error + Send>)?;
+
+            Ok(if let Some(item) = item {
+                Some(self.intern::<H, T, V>(item).await?)
+            } else {
+                None
+            })
+        })
+    }
+
+    #[cfg(feature = "chunked")]
+    fn intern_chunked<'a, H: Hasher<A>, T, U: Rehydrate<T>>(
+        &'a self,
+        item: T,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Resource<T, Chunked<U, A, H>, A>, Box<dyn Error + Send>>>
+                + Send
+                + 'a,
+        >,
+    >
+    where
+        Self: Sync,
+        T: Send + 'static,
+        U::Rehydrate: Send + 'static,
+        U::RehydrateError: Send + 'static,
+        U::Dump: Send + 'static,
+        U::DumpError: Error + Send + 'static,
+        A: Any + Send + 'static,
+        A::Hash: Serialize + DeserializeOwned + Eq + Hash + Clone + Send + 'static,
+        Self::Put: Send + 'static,
+        <Self::Put as TryFuture>::Error: Error + Send + 'static,
+    {
+        let item = U::dump(item);
+
+        Box::pin(async move {
+            let item = item
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn Error + Send>)?;
+
+            let split = split::<A, H>(&item).map_err(|e| Box::new(e) as Box<dyn Error + Send>)?;
+
+            let puts = split
+                .chunks
+                .into_iter()
+                .map(|(hash, chunk)| self.put_raw(hash, chunk))
+                .collect::<Vec<_>>();
+
+            try_join_all(puts)
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn Error + Send>)?;
+
+            let hash = self
+                .put::<H>(split.manifest)
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn Error + Send>)?;
+
+            Ok(Resource::new(hash))
+        })
+    }
+}
+
+impl<A: Algorithm, T: ResourceStore<A>> ResourceStoreExt<A> for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_util::TestHasher, Convert, MemoryStore, Sha256};
+    use futures::executor::block_on;
+
+    #[test]
+    fn erased_store_interns() {
+        let store = MemoryStore::<Sha256>::new();
+        let erased = store.clone().erase_store();
+
+        let resource: Resource<Vec<u8>, Convert, Sha256> =
+            block_on(erased.intern::<TestHasher, _, _>(b"data".to_vec())).unwrap();
+
+        assert!(block_on(store.contains(resource.hash())).unwrap());
+    }
+}
@@ -0,0 +1,317 @@
+use super::{
+    hash::{Algorithm, Hasher},
+    manager::{ProviderHandle, ProviderMetadata},
+    provider::ResourceProvider,
+    Rehydrate, ResourceError,
+};
+use crate::Resource;
+use core::{
+    any::{Any, TypeId},
+    convert::Infallible,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use core_error::Error;
+use core_futures_io::AsyncRead;
+use futures::{
+    future::{poll_fn, ready, MapOk, Ready},
+    ready, Future, TryFuture, TryFutureExt,
+};
+use thiserror::Error;
+
+pub type ErasedReader = Pin<Box<dyn AsyncRead<Error = Box<dyn Error + Send>> + Send>>;
+
+pub struct BufferReader {
+    data: Vec<u8>,
+    position: usize,
+}
+
+impl BufferReader {
+    pub fn new(data: Vec<u8>) -> Self {
+        BufferReader { data, position: 0 }
+    }
+}
+
+impl AsyncRead for BufferReader {
+    type Error = Infallible;
+
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _: &mut Context,
+        buffer: &mut [u8],
+    ) -> Poll<Result<usize, Self::Error>> {
+        let this = &mut *self;
+
+        let remaining = &this.data[this.position..];
+        let len = remaining.len().min(buffer.len());
+
+        buffer[..len].copy_from_slice(&remaining[..len]);
+        this.position += len;
+
+        Poll::Ready(Ok(len))
+    }
+}
+
+struct ReaderEraser<R: AsyncRead> {
+    reader: R,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for ReaderEraser<R>
+where
+    R::Error: Error + Send + 'static,
+{
+    type Error = Box<dyn Error + Send>;
+
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buffer: &mut [u8],
+    ) -> Poll<Result<usize, Self::Error>> {
+        Pin::new(&mut self.reader)
+            .poll_read(cx, buffer)
+            .map_err(|e| Box::new(e) as Box<dyn Error + Send>)
+    }
+}
+
+pub fn erase_reader<R: AsyncRead + Unpin + Send + 'static>(reader: R) -> ErasedReader
+where
+    R::Error: Error + Send + 'static,
+{
+    Box::pin(ReaderEraser { reader })
+}
+
+pub async fn read_to_end(mut reader: ErasedReader) -> Result<Vec<u8>, Box<dyn Error + Send>> {
+    let mut data = vec![];
+    let mut buffer = [0u8; 8192];
+
+    loop {
+        let len = poll_fn(|cx| reader.as_mut().poll_read(cx, &mut buffer)).await?;
+
+        if len == 0 {
+            return Ok(data);
+        }
+
+        data.extend_from_slice(&buffer[..len]);
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("streamed data does not match the requested hash")]
+pub struct StreamMismatch;
+
+pub struct VerifyingReader<A: Algorithm, H> {
+    reader: ErasedReader,
+    hasher: H,
+    hash: A::Hash,
+}
+
+impl<A: Algorithm, H: Hasher<A>> VerifyingReader<A, H> {
+    pub fn new(reader: ErasedReader, hash: A::Hash) -> Self {
+        VerifyingReader {
+            reader,
+            hasher: H::new(),
+            hash,
+        }
+    }
+}
+
+impl<A: Algorithm, H> Unpin for VerifyingReader<A, H> {}
+
+impl<A: Algorithm, H: Hasher<A>> AsyncRead for VerifyingReader<A, H>
+where
+    A::Hash: PartialEq,
+{
+    type Error = Box<dyn Error + Send>;
+
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buffer: &mut [u8],
+    ) -> Poll<Result<usize, Self::Error>> {
+        let this = &mut *self;
+
+        let len = ready!(this.reader.as_mut().poll_read(cx, buffer))?;
+
+        if len > 0 {
+            this.hasher.write(&buffer[..len]);
+        } else if !buffer.is_empty() && this.hasher.hash() != this.hash {
+            return Poll::Ready(Err(Box::new(StreamMismatch)));
+        }
+
+        Poll::Ready(Ok(len))
+    }
+}
+
+pub fn verify_reader<A: Algorithm + 'static, H: Hasher<A> + Send + 'static>(
+    reader: ErasedReader,
+    hash: A::Hash,
+) -> ErasedReader
+where
+    A::Hash: PartialEq + Send,
+{
+    Box::pin(VerifyingReader::<A, H>::new(reader, hash))
+}
+
+pub trait StreamingResourceProvider<A: Algorithm> {
+    type Fetch: TryFuture<Ok = Option<ErasedReader>>;
+
+    fn fetch_stream(&self, hash: A::Hash) -> Self::Fetch;
+}
+
+pub struct Buffered<P>(pub P);
+
+impl<A: Algorithm, P: ResourceProvider<A>> StreamingResourceProvider<A> for Buffered<P> {
+    type Fetch = MapOk<P::Fetch, fn(Option<Vec<u8>>) -> Option<ErasedReader>>;
+
+    fn fetch_stream(&self, hash: A::Hash) -> Self::Fetch {
+        self.0.fetch(hash).map_ok(
+            (|data: Option<Vec<u8>>| data.map(|data| erase_reader(BufferReader::new(data))))
+                as fn(Option<Vec<u8>>) -> Option<ErasedReader>,
+        )
+    }
+}
+
+pub trait StreamingRehydrate<T>: Sized {
+    type RehydrateError;
+    type Rehydrate: Future<Output = Result<T, Self::RehydrateError>>;
+
+    fn rehydrate_stream(reader: ErasedReader) -> Self::Rehydrate;
+}
+
+pub struct Buffer<U>(PhantomData<U>);
+
+impl<T, U: Rehydrate<T>> StreamingRehydrate<T> for Buffer<U>
+where
+    T: Send + 'static,
+    U::Rehydrate: Send + 'static,
+    U::RehydrateError: Error + Send + 'static,
+{
+    type RehydrateError = Box<dyn Error + Send>;
+    type Rehydrate = Pin<Box<dyn Future<Output = Result<T, Self::RehydrateError>> + Send>>;
+
+    fn rehydrate_stream(reader: ErasedReader) -> Self::Rehydrate {
+        Box::pin(async move {
+            let data = read_to_end(reader).await?;
+
+            U::rehydrate(data)
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn Error + Send>)
+        })
+    }
+}
+
+pub struct Streamed;
+
+impl Rehydrate<ErasedReader> for Streamed {
+    type RehydrateError = Infallible;
+    type Rehydrate = Ready<Result<ErasedReader, Self::RehydrateError>>;
+    type DumpError = Box<dyn Error + Send>;
+    type Dump = Pin<Box<dyn Future<Output = Result<Vec<u8>, Self::DumpError>> + Send>>;
+
+    fn rehydrate(data: Vec<u8>) -> Self::Rehydrate {
+        ready(Ok(erase_reader(BufferReader::new(data))))
+    }
+    fn dump(data: ErasedReader) -> Self::Dump {
+        Box::pin(read_to_end(data))
+    }
+}
+
+impl StreamingRehydrate<ErasedReader> for Streamed {
+    type RehydrateError = Infallible;
+    type Rehydrate = Ready<Result<ErasedReader, Self::RehydrateError>>;
+
+    fn rehydrate_stream(reader: ErasedReader) -> Self::Rehydrate {
+        ready(Ok(reader))
+    }
+}
+
+pub trait StreamingResourceProviderExt<A: Algorithm>: StreamingResourceProvider<A> {
+    fn fetch_rehydrate<T, U: Rehydrate<T> + StreamingRehydrate<T>>(
+        &self,
+        resource: Resource<T, U, A>,
+    ) -> Pin<Box<dyn Future<Output = Result<Option<T>, Box<dyn Error + Send>>> + Send>>
+    where
+        T: 'static,
+        A::Hash: Clone,
+        Self::Fetch: Send + 'static,
+        <Self::Fetch as TryFuture>::Error: Error + Send + 'static,
+        <U as StreamingRehydrate<T>>::Rehydrate: Send + 'static,
+        <U as StreamingRehydrate<T>>::RehydrateError: Error + Send + 'static,
+    {
+        let fetch = self.fetch_stream(resource.hash()).into_future();
+
+        Box::pin(async move {
+            let reader = fetch
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn Error + Send>)?;
+
+            Ok(if let Some(reader) = reader {
+                Some(
+                    <U as StreamingRehydrate<T>>::rehydrate_stream(reader)
+                        .await
+                        .map_err(|e| Box::new(e) as Box<dyn Error + Send>)?,
+                )
+            } else {
+                None
+            })
+        })
+    }
+}
+
+impl<A: Algorithm, P: StreamingResourceProvider<A>> StreamingResourceProviderExt<A> for P {}
+
+pub trait StreamingResourceManager {
+    type FetchStream: Future<Output = Result<Option<ErasedReader>, ResourceError<Infallible>>>;
+
+    fn fetch_stream(
+        &self,
+        algo: TypeId,
+        hash: Box<dyn FnMut() -> Box<dyn Any + Send> + Send>,
+    ) -> Self::FetchStream;
+}
+
+pub trait StreamingResourceManagerExt: StreamingResourceManager {
+    fn fetch_rehydrate_stream<A: Algorithm + Any, T, U: StreamingRehydrate<T>>(
+        &self,
+        resource: Resource<T, U, A>,
+    ) -> Pin<Box<dyn Future<Output = Result<Option<T>, ResourceError<U::RehydrateError>>> + Send>>
+    where
+        A::Hash: Clone + Send + 'static,
+        Self::FetchStream: Send + 'static,
+        U::Rehydrate: Send + 'static,
+        U::RehydrateError: 'static,
+        T: 'static,
+    {
+        let hash = resource.hash();
+        let fetch = self.fetch_stream(TypeId::of::<A>(), Box::new(move || Box::new(hash.clone())));
+
+        Box::pin(async move {
+            Ok(match fetch.await.map_err(ResourceError::cast)? {
+                Some(reader) => Some(
+                    U::rehydrate_stream(reader)
+                        .await
+                        .map_err(ResourceError::Rehydration)?,
+                ),
+                None => None,
+            })
+        })
+    }
+}
+
+impl<T: StreamingResourceManager> StreamingResourceManagerExt for T {}
+
+pub trait StreamingRegistrant<A, T>
+where
+    A: Algorithm,
+    T: StreamingResourceProvider<A>,
+{
+    type Handle: ProviderHandle;
+    type Register: TryFuture<Ok = Self::Handle>;
+
+    fn register_streaming_provider(
+        &mut self,
+        provider: T,
+        metadata: ProviderMetadata,
+    ) -> Self::Register;
+}
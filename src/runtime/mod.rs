@@ -1,4 +1,12 @@
-use crate::{resource::ResourceError, Convert, CoreError, Resource, Sha256};
+use crate::{
+    resource::{
+        domain::{Domain, Reinterpret},
+        graph::{Link, Links},
+        hash::{Algorithm, Hasher},
+        ResourceError,
+    },
+    Convert, CoreError, Resource, Sha256,
+};
 use core::{convert::Infallible, marker::PhantomData, pin::Pin};
 use core_error::Error;
 use core_futures_io::{AsyncRead, AsyncWrite};
@@ -80,15 +88,25 @@ impl From<Wasm> for Vec<u8> {
     }
 }
 
+impl Domain for Wasm {
+    const TAG: &'static str = "wasm";
+}
+
+impl<A: Algorithm> Links<A> for Wasm {
+    fn links(&self) -> Vec<Link<A>> {
+        vec![]
+    }
+}
+
 impl<T> From<ModuleResource<T>> for WasmResource {
     fn from(resource: ModuleResource<T>) -> Self {
-        Resource::new(resource.hash())
+        resource.reinterpret()
     }
 }
 
 impl<T> From<WasmResource> for ModuleResource<T> {
     fn from(resource: WasmResource) -> Self {
-        ModuleResource::new(resource.hash())
+        resource.reinterpret()
     }
 }
 
@@ -112,6 +130,27 @@ impl<T> From<Module<T>> for Vec<u8> {
     }
 }
 
+impl<T: Domain> Domain for Module<T> {
+    const TAG: &'static str = "module";
+
+    fn write_tag<A: Algorithm, H: Hasher<A>>(hasher: &mut H) {
+        Wasm::write_tag(hasher);
+        hasher.write(&(Self::TAG.len() as u64).to_be_bytes());
+        hasher.write(Self::TAG.as_bytes());
+        T::write_tag(hasher);
+    }
+}
+
+impl<T> Reinterpret<Wasm> for Module<T> {}
+
+impl<T> Reinterpret<Module<T>> for Wasm {}
+
+impl<A: Algorithm, T> Links<A> for Module<T> {
+    fn links(&self) -> Vec<Link<A>> {
+        vec![]
+    }
+}
+
 #[derive(Error, Debug)]
 #[bounds(where
     T: Error + 'static,
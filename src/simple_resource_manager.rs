@@ -1,44 +1,260 @@
 use crate::resource::{
     hash::Algorithm,
-    manager::{ResourceManager, ResourceRegistrant},
+    manager::{
+        ProviderHandle, ProviderMetadata, ResourceManager, ResourceRegistrant, StoreRegistrant,
+    },
     provider::ResourceProvider,
-    ResourceError,
+    store::ResourceStore,
+    stream::{
+        erase_reader, read_to_end, BufferReader, ErasedReader, StreamingRegistrant,
+        StreamingResourceManager, StreamingResourceProvider,
+    },
+    ProviderErrors, ProviderFailure, ResourceError,
 };
 use core_error::Error;
 use futures::{
-    future::ready, lock::Mutex, stream::iter, Future, FutureExt, StreamExt, TryFuture, TryFutureExt,
+    future::{select, try_join_all, Either},
+    lock::Mutex,
+    stream::FuturesUnordered,
+    Future, FutureExt, StreamExt, TryFuture, TryFutureExt,
 };
 use protocol::allocated::ProtocolError;
 use std::{
     any::{Any, TypeId},
+    cmp::Reverse,
     collections::HashMap,
     convert::Infallible,
     pin::Pin,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex as SyncMutex,
+    },
+    time::Duration,
 };
 
+type ProviderFetch<T> =
+    Pin<Box<dyn Future<Output = Result<Option<T>, Box<dyn Error + Send>>> + Send>>;
+
+type Start<T> = Box<dyn FnOnce() -> ProviderFetch<T> + Send>;
+
+#[derive(Clone)]
+enum Source {
+    Buffered(Arc<dyn Fn(Box<dyn Any + Send>) -> ProviderFetch<Vec<u8>> + Send + Sync>),
+    Streaming(Arc<dyn Fn(Box<dyn Any + Send>) -> ProviderFetch<ErasedReader> + Send + Sync>),
+}
+
+impl Source {
+    fn buffered(&self, hash: Box<dyn Any + Send>) -> Start<Vec<u8>> {
+        match self.clone() {
+            Source::Buffered(fetch) => Box::new(move || (fetch)(hash)),
+            Source::Streaming(fetch) => Box::new(move || {
+                let fetch = (fetch)(hash);
+
+                Box::pin(async move {
+                    Ok::<_, Box<dyn Error + Send>>(match fetch.await? {
+                        Some(reader) => Some(read_to_end(reader).await?),
+                        None => None,
+                    })
+                }) as ProviderFetch<Vec<u8>>
+            }),
+        }
+    }
+
+    fn streaming(&self, hash: Box<dyn Any + Send>) -> Start<ErasedReader> {
+        match self.clone() {
+            Source::Buffered(fetch) => Box::new(move || {
+                let fetch = (fetch)(hash);
+
+                Box::pin(async move {
+                    Ok::<_, Box<dyn Error + Send>>(
+                        fetch
+                            .await?
+                            .map(|data| erase_reader(BufferReader::new(data))),
+                    )
+                }) as ProviderFetch<ErasedReader>
+            }),
+            Source::Streaming(fetch) => Box::new(move || (fetch)(hash)),
+        }
+    }
+}
+
+struct ProviderEntry {
+    id: u64,
+    metadata: ProviderMetadata,
+    source: Source,
+}
+
+type Providers = Arc<Mutex<HashMap<TypeId, Vec<ProviderEntry>>>>;
+
+type StorePut = Box<
+    dyn Fn(
+            Box<dyn Any + Send>,
+            Vec<u8>,
+        ) -> Pin<Box<dyn Future<Output = Result<(), Box<dyn Error + Send>>> + Send>>
+        + Send,
+>;
+
+struct StoreEntry {
+    id: u64,
+    metadata: ProviderMetadata,
+    put: StorePut,
+}
+
+type Stores = Arc<Mutex<HashMap<TypeId, Vec<StoreEntry>>>>;
+
+pub type HedgeTimer =
+    Arc<dyn Fn(Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+
+#[derive(Clone)]
+pub enum FetchStrategy {
+    Sequential,
+    Concurrent,
+    Hedged { delay: Duration, timer: HedgeTimer },
+}
+
+impl Default for FetchStrategy {
+    fn default() -> Self {
+        FetchStrategy::Sequential
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    FailFast,
+    Continue,
+}
+
+impl Default for ErrorPolicy {
+    fn default() -> Self {
+        ErrorPolicy::FailFast
+    }
+}
+
+struct Failures {
+    policy: ErrorPolicy,
+    names: Vec<Option<String>>,
+    errors: Vec<ProviderFailure>,
+}
+
+impl Failures {
+    fn record<T>(
+        &mut self,
+        index: usize,
+        result: Result<Option<T>, Box<dyn Error + Send>>,
+    ) -> Result<Option<T>, ResourceError<Infallible>> {
+        match result {
+            Ok(data) => Ok(data),
+            Err(error) => match self.policy {
+                ErrorPolicy::FailFast => Err(ResourceError::Provider(error)),
+                ErrorPolicy::Continue => {
+                    self.errors.push(ProviderFailure {
+                        index,
+                        name: self.names[index].clone(),
+                        error,
+                    });
+                    Ok(None)
+                }
+            },
+        }
+    }
+
+    fn finish<T>(self) -> Result<Option<T>, ResourceError<Infallible>> {
+        if self.errors.is_empty() {
+            Ok(None)
+        } else {
+            Err(ResourceError::Providers(ProviderErrors(self.errors)))
+        }
+    }
+}
+
+fn launch<T: Send + 'static>(
+    index: usize,
+    start: Start<T>,
+) -> impl Future<Output = (usize, Result<Option<T>, Box<dyn Error + Send>>)> + Send {
+    (start)().map(move |result| (index, result))
+}
+
+impl FetchStrategy {
+    async fn run<T: Send + 'static>(
+        self,
+        policy: ErrorPolicy,
+        names: Vec<Option<String>>,
+        starts: Vec<Start<T>>,
+    ) -> Result<Option<T>, ResourceError<Infallible>> {
+        let mut failures = Failures {
+            policy,
+            names,
+            errors: vec![],
+        };
+
+        let starts = starts.into_iter().enumerate();
+
+        match self {
+            FetchStrategy::Sequential => {
+                for (index, start) in starts {
+                    let (index, result) = launch(index, start).await;
+
+                    if let Some(data) = failures.record(index, result)? {
+                        return Ok(Some(data));
+                    }
+                }
+            }
+            FetchStrategy::Concurrent => {
+                let mut pending = starts
+                    .map(|(index, start)| launch(index, start))
+                    .collect::<FuturesUnordered<_>>();
+
+                while let Some((index, result)) = pending.next().await {
+                    if let Some(data) = failures.record(index, result)? {
+                        return Ok(Some(data));
+                    }
+                }
+            }
+            FetchStrategy::Hedged { delay, timer } => {
+                let mut remaining = starts.peekable();
+                let mut pending = FuturesUnordered::new();
+
+                loop {
+                    if pending.is_empty() {
+                        match remaining.next() {
+                            Some((index, start)) => pending.push(launch(index, start)),
+                            None => break,
+                        }
+                    }
+
+                    let completed = if remaining.peek().is_none() {
+                        pending.next().await
+                    } else {
+                        match select(pending.next(), (timer)(delay)).await {
+                            Either::Left((result, _)) => result,
+                            Either::Right(_) => None,
+                        }
+                    };
+
+                    if let Some((index, result)) = completed {
+                        if let Some(data) = failures.record(index, result)? {
+                            return Ok(Some(data));
+                        }
+                    }
+
+                    if let Some((index, start)) = remaining.next() {
+                        pending.push(launch(index, start));
+                    }
+                }
+            }
+        }
+
+        failures.finish()
+    }
+}
+
 #[derive(Clone)]
 pub struct SimpleResourceManager {
-    providers: Arc<
-        Mutex<
-            HashMap<
-                TypeId,
-                Vec<
-                    Box<
-                        dyn Fn(
-                                Box<dyn Any + Send>,
-                            ) -> Pin<
-                                Box<
-                                    dyn Future<
-                                            Output = Result<Option<Vec<u8>>, Box<dyn Error + Send>>,
-                                        > + Send,
-                                >,
-                            > + Send,
-                    >,
-                >,
-            >,
-        >,
-    >,
+    providers: Providers,
+    next_id: Arc<AtomicU64>,
+    stores: Stores,
+    strategy: FetchStrategy,
+    policy: ErrorPolicy,
 }
 
 impl ResourceManager for SimpleResourceManager {
@@ -48,56 +264,38 @@ impl ResourceManager for SimpleResourceManager {
     fn fetch(
         &self,
         algo: TypeId,
-        mut hash: Box<dyn FnMut() -> Box<dyn Any + Send> + Send>,
+        hash: Box<dyn FnMut() -> Box<dyn Any + Send> + Send>,
     ) -> Self::Fetch {
-        let providers = self.providers.clone();
+        Box::pin(self.query(algo, hash, Source::buffered))
+    }
 
-        Box::pin(
-            async move {
-                let providers = providers.lock().await;
+    fn publish(
+        &self,
+        algo: TypeId,
+        mut hash: Box<dyn FnMut() -> Box<dyn Any + Send> + Send>,
+        data: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), ResourceError<Infallible>>> + Send>> {
+        let stores = self.stores.clone();
 
-                let providers = providers
-                    .get(&algo)
-                    .ok_or(ResourceError::<Infallible>::UnknownAlgorithm)?;
+        Box::pin(async move {
+            let futures = stores
+                .lock()
+                .await
+                .get(&algo)
+                .into_iter()
+                .flatten()
+                .filter(|store| !store.metadata.read_only)
+                .map(|store| (store.put)(hash(), data.clone()))
+                .collect::<Vec<_>>();
 
-                let futures = providers
-                    .iter()
-                    .map(|provider| (provider)(hash()).into_stream())
-                    .collect::<Vec<_>>();
-
-                let futures = iter(futures.into_iter()).flatten();
-
-                Ok({
-                    let future: Pin<
-                        Box<
-                            dyn Future<
-                                    Output = Option<Result<Option<Vec<u8>>, Box<dyn Error + Send>>>,
-                                > + Send,
-                        >,
-                    > = Box::pin(
-                        futures
-                            .skip_while(|item| {
-                                ready(!(item.is_err() || item.as_ref().unwrap().is_some()))
-                            })
-                            .into_future()
-                            .map(|(data, _)| data),
-                    );
-                    future
-                })
+            if futures.is_empty() {
+                return Err(ResourceError::NoStore);
             }
-            .then(
-                |item: Result<
-                    Pin<
-                        Box<
-                            dyn Future<
-                                    Output = Option<Result<Option<Vec<u8>>, Box<dyn Error + Send>>>,
-                                > + Send,
-                        >,
-                    >,
-                    ResourceError<Infallible>,
-                >| async { Ok(item?.await.transpose()?.flatten()) },
-            ),
-        )
+
+            try_join_all(futures).await.map_err(ResourceError::Store)?;
+
+            Ok(())
+        })
     }
 }
 
@@ -108,26 +306,211 @@ where
     A: Algorithm + Send + 'static,
     <T::Fetch as TryFuture>::Error: Error + Send,
 {
-    type Register = Pin<Box<dyn Future<Output = Result<(), ProtocolError>> + Send>>;
+    type Handle = SimpleProviderHandle;
+    type Register =
+        Pin<Box<dyn Future<Output = Result<SimpleProviderHandle, ProtocolError>> + Send>>;
+
+    fn register_provider(&mut self, provider: T, metadata: ProviderMetadata) -> Self::Register {
+        let provider = SyncMutex::new(provider);
+
+        self.register::<A>(
+            metadata,
+            Source::Buffered(Arc::new(move |any| {
+                let fut = provider
+                    .lock()
+                    .unwrap()
+                    .fetch(*Box::<dyn Any>::downcast(any).unwrap());
+
+                Box::pin(async move {
+                    fut.into_future()
+                        .await
+                        .map_err(|e| Box::new(e) as Box<dyn Error + Send>)
+                })
+            })),
+        )
+    }
+}
+
+impl StreamingResourceManager for SimpleResourceManager {
+    type FetchStream = Pin<
+        Box<dyn Future<Output = Result<Option<ErasedReader>, ResourceError<Infallible>>> + Send>,
+    >;
+
+    fn fetch_stream(
+        &self,
+        algo: TypeId,
+        hash: Box<dyn FnMut() -> Box<dyn Any + Send> + Send>,
+    ) -> Self::FetchStream {
+        Box::pin(self.query(algo, hash, Source::streaming))
+    }
+}
+
+impl<A, T> StreamingRegistrant<A, T> for SimpleResourceManager
+where
+    T: StreamingResourceProvider<A> + Send + Sized + 'static,
+    T::Fetch: Send + 'static,
+    A: Algorithm + Send + 'static,
+    <T::Fetch as TryFuture>::Error: Error + Send,
+{
+    type Handle = SimpleProviderHandle;
+    type Register =
+        Pin<Box<dyn Future<Output = Result<SimpleProviderHandle, ProtocolError>> + Send>>;
+
+    fn register_streaming_provider(
+        &mut self,
+        provider: T,
+        metadata: ProviderMetadata,
+    ) -> Self::Register {
+        let provider = SyncMutex::new(provider);
+
+        self.register::<A>(
+            metadata,
+            Source::Streaming(Arc::new(move |any| {
+                let fut = provider
+                    .lock()
+                    .unwrap()
+                    .fetch_stream(*Box::<dyn Any>::downcast(any).unwrap());
+
+                Box::pin(async move {
+                    fut.into_future()
+                        .await
+                        .map_err(|e| Box::new(e) as Box<dyn Error + Send>)
+                })
+            })),
+        )
+    }
+}
 
-    fn register_provider(&mut self, provider: T) -> Self::Register {
+pub struct SimpleProviderHandle {
+    providers: Providers,
+    algo: TypeId,
+    id: u64,
+}
+
+impl ProviderHandle for SimpleProviderHandle {
+    type Unregister = Pin<Box<dyn Future<Output = Result<(), ProtocolError>> + Send>>;
+    type Reprioritize = Pin<Box<dyn Future<Output = Result<(), ProtocolError>> + Send>>;
+
+    fn unregister(&mut self) -> Self::Unregister {
         let providers = self.providers.clone();
+        let algo = self.algo;
+        let id = self.id;
 
         Box::pin(async move {
             let mut providers = providers.lock().await;
 
-            providers
-                .entry(TypeId::of::<A>())
-                .or_insert(vec![])
-                .push(Box::new(move |any| {
-                    let fut = provider.fetch(*Box::<dyn Any>::downcast(any).unwrap());
+            if let Some(entries) = providers.get_mut(&algo) {
+                entries.retain(|entry| entry.id != id);
+            }
+
+            Ok(())
+        })
+    }
+
+    fn reprioritize(&mut self, priority: i32) -> Self::Reprioritize {
+        let providers = self.providers.clone();
+        let algo = self.algo;
+        let id = self.id;
+
+        Box::pin(async move {
+            let mut providers = providers.lock().await;
+
+            if let Some(entries) = providers.get_mut(&algo) {
+                for entry in entries.iter_mut().filter(|entry| entry.id == id) {
+                    entry.metadata.priority = priority;
+                }
+                entries.sort_by_key(|entry| Reverse(entry.metadata.priority));
+            }
+
+            Ok(())
+        })
+    }
+}
+
+impl<A, T> StoreRegistrant<A, T> for SimpleResourceManager
+where
+    T: ResourceStore<A> + Send + Sized + 'static,
+    T::Put: Send + 'static,
+    A: Algorithm + Send + 'static,
+    <T::Put as TryFuture>::Error: Error + Send,
+{
+    type Handle = SimpleStoreHandle;
+    type Register = Pin<Box<dyn Future<Output = Result<SimpleStoreHandle, ProtocolError>> + Send>>;
+
+    fn register_store(&mut self, store: T, metadata: ProviderMetadata) -> Self::Register {
+        let stores = self.stores.clone();
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let algo = TypeId::of::<A>();
+
+        Box::pin(async move {
+            let mut entries = stores.lock().await;
+
+            let entries = entries.entry(algo).or_insert(vec![]);
+            entries.push(StoreEntry {
+                id,
+                metadata,
+                put: Box::new(move |any, data| {
+                    let fut = store.put_raw(*Box::<dyn Any>::downcast(any).unwrap(), data);
 
                     Box::pin(async move {
                         fut.into_future()
                             .await
                             .map_err(|e| Box::new(e) as Box<dyn Error + Send>)
                     })
-                }));
+                }),
+            });
+            entries.sort_by_key(|entry| Reverse(entry.metadata.priority));
+
+            Ok(SimpleStoreHandle {
+                stores: stores.clone(),
+                algo,
+                id,
+            })
+        })
+    }
+}
+
+pub struct SimpleStoreHandle {
+    stores: Stores,
+    algo: TypeId,
+    id: u64,
+}
+
+impl ProviderHandle for SimpleStoreHandle {
+    type Unregister = Pin<Box<dyn Future<Output = Result<(), ProtocolError>> + Send>>;
+    type Reprioritize = Pin<Box<dyn Future<Output = Result<(), ProtocolError>> + Send>>;
+
+    fn unregister(&mut self) -> Self::Unregister {
+        let stores = self.stores.clone();
+        let algo = self.algo;
+        let id = self.id;
+
+        Box::pin(async move {
+            let mut stores = stores.lock().await;
+
+            if let Some(entries) = stores.get_mut(&algo) {
+                entries.retain(|entry| entry.id != id);
+            }
+
+            Ok(())
+        })
+    }
+
+    fn reprioritize(&mut self, priority: i32) -> Self::Reprioritize {
+        let stores = self.stores.clone();
+        let algo = self.algo;
+        let id = self.id;
+
+        Box::pin(async move {
+            let mut stores = stores.lock().await;
+
+            if let Some(entries) = stores.get_mut(&algo) {
+                for entry in entries.iter_mut().filter(|entry| entry.id == id) {
+                    entry.metadata.priority = priority;
+                }
+                entries.sort_by_key(|entry| Reverse(entry.metadata.priority));
+            }
+
             Ok(())
         })
     }
@@ -137,6 +520,409 @@ impl SimpleResourceManager {
     pub fn new() -> Self {
         SimpleResourceManager {
             providers: Arc::new(Mutex::new(HashMap::new())),
+            next_id: Arc::new(AtomicU64::new(0)),
+            stores: Arc::new(Mutex::new(HashMap::new())),
+            strategy: FetchStrategy::default(),
+            policy: ErrorPolicy::default(),
+        }
+    }
+
+    fn query<T: Send + 'static>(
+        &self,
+        algo: TypeId,
+        mut hash: Box<dyn FnMut() -> Box<dyn Any + Send> + Send>,
+        start: fn(&Source, Box<dyn Any + Send>) -> Start<T>,
+    ) -> impl Future<Output = Result<Option<T>, ResourceError<Infallible>>> + Send {
+        let providers = self.providers.clone();
+        let strategy = self.strategy.clone();
+        let policy = self.policy;
+
+        async move {
+            let (names, starts): (Vec<_>, Vec<_>) = {
+                let providers = providers.lock().await;
+
+                providers
+                    .get(&algo)
+                    .ok_or(ResourceError::<Infallible>::UnknownAlgorithm)?
+                    .iter()
+                    .map(|provider| {
+                        (
+                            provider.metadata.name.clone(),
+                            start(&provider.source, hash()),
+                        )
+                    })
+                    .unzip()
+            };
+
+            strategy.run(policy, names, starts).await
+        }
+    }
+
+    fn register<A: 'static>(
+        &self,
+        metadata: ProviderMetadata,
+        source: Source,
+    ) -> Pin<Box<dyn Future<Output = Result<SimpleProviderHandle, ProtocolError>> + Send>> {
+        let providers = self.providers.clone();
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let algo = TypeId::of::<A>();
+
+        Box::pin(async move {
+            let mut entries = providers.lock().await;
+
+            let entries = entries.entry(algo).or_insert(vec![]);
+            entries.push(ProviderEntry {
+                id,
+                metadata,
+                source,
+            });
+            entries.sort_by_key(|entry| Reverse(entry.metadata.priority));
+
+            Ok(SimpleProviderHandle {
+                providers: providers.clone(),
+                algo,
+                id,
+            })
+        })
+    }
+
+    pub fn with_strategy(strategy: FetchStrategy) -> Self {
+        SimpleResourceManager {
+            strategy,
+            ..SimpleResourceManager::new()
+        }
+    }
+
+    pub fn set_strategy(&mut self, strategy: FetchStrategy) {
+        self.strategy = strategy;
+    }
+
+    pub fn set_error_policy(&mut self, policy: ErrorPolicy) {
+        self.policy = policy;
+    }
+
+    pub fn stores<A: Algorithm + 'static>(&self) -> impl Future<Output = Vec<ProviderMetadata>> {
+        let stores = self.stores.clone();
+
+        async move {
+            stores
+                .lock()
+                .await
+                .get(&TypeId::of::<A>())
+                .map(|entries| entries.iter().map(|entry| entry.metadata.clone()).collect())
+                .unwrap_or_default()
+        }
+    }
+
+    pub fn providers<A: Algorithm + 'static>(&self) -> impl Future<Output = Vec<ProviderMetadata>> {
+        let providers = self.providers.clone();
+
+        async move {
+            providers
+                .lock()
+                .await
+                .get(&TypeId::of::<A>())
+                .map(|entries| entries.iter().map(|entry| entry.metadata.clone()).collect())
+                .unwrap_or_default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_util::TestError, MemoryStore, Sha256, Sha256Sum};
+    use futures::{
+        executor::block_on,
+        future::{pending, ready},
+    };
+    use std::sync::atomic::AtomicUsize;
+
+    #[derive(Clone, Copy)]
+    enum Response {
+        Hit,
+        Miss,
+        Fail,
+        Hang,
+    }
+
+    #[derive(Clone)]
+    struct Probe {
+        response: Response,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl Probe {
+        fn new(response: Response) -> Self {
+            Probe {
+                response,
+                calls: Arc::new(AtomicUsize::new(0)),
+            }
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    impl ResourceProvider<Sha256> for Probe {
+        type Fetch = Pin<Box<dyn Future<Output = Result<Option<Vec<u8>>, TestError>> + Send>>;
+
+        fn fetch(&self, _: Sha256Sum) -> Self::Fetch {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+
+            match self.response {
+                Response::Hit => Box::pin(ready(Ok(Some(b"data".to_vec())))),
+                Response::Miss => Box::pin(ready(Ok(None))),
+                Response::Fail => Box::pin(ready(Err(TestError))),
+                Response::Hang => Box::pin(pending()),
+            }
+        }
+    }
+
+    fn manager(strategy: FetchStrategy, probes: &[&Probe]) -> SimpleResourceManager {
+        let mut manager = SimpleResourceManager::with_strategy(strategy);
+
+        for probe in probes {
+            block_on(ResourceRegistrant::<Sha256, _>::register_provider(
+                &mut manager,
+                (*probe).clone(),
+                ProviderMetadata::default(),
+            ))
+            .unwrap();
         }
+
+        manager
+    }
+
+    fn fetch(
+        manager: &SimpleResourceManager,
+    ) -> Result<Option<Vec<u8>>, ResourceError<Infallible>> {
+        block_on(ResourceManager::fetch(
+            manager,
+            TypeId::of::<Sha256>(),
+            Box::new(|| Box::new(Sha256Sum([0; 32])) as Box<dyn Any + Send>),
+        ))
+    }
+
+    fn immediate() -> HedgeTimer {
+        Arc::new(|_| Box::pin(ready(())))
+    }
+
+    fn never() -> HedgeTimer {
+        Arc::new(|_| Box::pin(pending()))
+    }
+
+    #[test]
+    fn sequential_starts_providers_on_demand() {
+        let miss = Probe::new(Response::Miss);
+        let hit = Probe::new(Response::Hit);
+        let unused = Probe::new(Response::Hit);
+
+        let manager = manager(FetchStrategy::Sequential, &[&miss, &hit, &unused]);
+
+        assert_eq!(fetch(&manager).unwrap(), Some(b"data".to_vec()));
+        assert_eq!((miss.calls(), hit.calls(), unused.calls()), (1, 1, 0));
+    }
+
+    #[test]
+    fn concurrent_first_hit_wins() {
+        let hang = Probe::new(Response::Hang);
+        let hit = Probe::new(Response::Hit);
+
+        let manager = manager(FetchStrategy::Concurrent, &[&hang, &hit]);
+
+        assert_eq!(fetch(&manager).unwrap(), Some(b"data".to_vec()));
+        assert_eq!((hang.calls(), hit.calls()), (1, 1));
+    }
+
+    #[test]
+    fn hedged_starts_next_provider_after_delay() {
+        let hang = Probe::new(Response::Hang);
+        let hit = Probe::new(Response::Hit);
+
+        let manager = manager(
+            FetchStrategy::Hedged {
+                delay: Duration::from_millis(100),
+                timer: immediate(),
+            },
+            &[&hang, &hit],
+        );
+
+        assert_eq!(fetch(&manager).unwrap(), Some(b"data".to_vec()));
+        assert_eq!((hang.calls(), hit.calls()), (1, 1));
+    }
+
+    #[test]
+    fn hedged_does_not_start_next_provider_early() {
+        let hit = Probe::new(Response::Hit);
+        let unused = Probe::new(Response::Hit);
+
+        let manager = manager(
+            FetchStrategy::Hedged {
+                delay: Duration::from_millis(100),
+                timer: never(),
+            },
+            &[&hit, &unused],
+        );
+
+        assert_eq!(fetch(&manager).unwrap(), Some(b"data".to_vec()));
+        assert_eq!((hit.calls(), unused.calls()), (1, 0));
+    }
+
+    #[test]
+    fn hedged_moves_on_after_miss() {
+        let miss = Probe::new(Response::Miss);
+        let hit = Probe::new(Response::Hit);
+
+        let manager = manager(
+            FetchStrategy::Hedged {
+                delay: Duration::from_millis(100),
+                timer: never(),
+            },
+            &[&miss, &hit],
+        );
+
+        assert_eq!(fetch(&manager).unwrap(), Some(b"data".to_vec()));
+        assert_eq!((miss.calls(), hit.calls()), (1, 1));
+    }
+
+    #[test]
+    fn hedged_miss_starts_next_provider_without_waiting() {
+        let hang = Probe::new(Response::Hang);
+        let miss = Probe::new(Response::Miss);
+        let hit = Probe::new(Response::Hit);
+        let fired = Arc::new(AtomicUsize::new(0));
+
+        let manager = manager(
+            FetchStrategy::Hedged {
+                delay: Duration::from_millis(100),
+                timer: Arc::new(move |_| {
+                    if fired.fetch_add(1, Ordering::SeqCst) == 0 {
+                        Box::pin(ready(())) as Pin<Box<dyn Future<Output = ()> + Send>>
+                    } else {
+                        Box::pin(pending())
+                    }
+                }),
+            },
+            &[&hang, &miss, &hit],
+        );
+
+        assert_eq!(fetch(&manager).unwrap(), Some(b"data".to_vec()));
+        assert_eq!((hang.calls(), miss.calls(), hit.calls()), (1, 1, 1));
+    }
+
+    #[test]
+    fn fail_fast_stops_at_first_error() {
+        let fail = Probe::new(Response::Fail);
+        let hit = Probe::new(Response::Hit);
+
+        let manager = manager(FetchStrategy::Sequential, &[&fail, &hit]);
+
+        assert!(matches!(fetch(&manager), Err(ResourceError::Provider(_))));
+        assert_eq!(hit.calls(), 0);
+    }
+
+    struct Streamer;
+
+    impl StreamingResourceProvider<Sha256> for Streamer {
+        type Fetch = Pin<Box<dyn Future<Output = Result<Option<ErasedReader>, TestError>> + Send>>;
+
+        fn fetch_stream(&self, _: Sha256Sum) -> Self::Fetch {
+            Box::pin(ready(Ok(Some(erase_reader(BufferReader::new(
+                b"stream".to_vec(),
+            ))))))
+        }
+    }
+
+    fn fetch_stream(
+        manager: &SimpleResourceManager,
+    ) -> Result<Option<Vec<u8>>, ResourceError<Infallible>> {
+        let reader = block_on(StreamingResourceManager::fetch_stream(
+            manager,
+            TypeId::of::<Sha256>(),
+            Box::new(|| Box::new(Sha256Sum([0; 32])) as Box<dyn Any + Send>),
+        ))?;
+
+        Ok(reader.map(|reader| block_on(read_to_end(reader)).unwrap()))
+    }
+
+    #[test]
+    fn streaming_providers_serve_both_paths() {
+        let mut manager = SimpleResourceManager::new();
+
+        block_on(
+            StreamingRegistrant::<Sha256, _>::register_streaming_provider(
+                &mut manager,
+                Streamer,
+                ProviderMetadata::default(),
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(fetch_stream(&manager).unwrap(), Some(b"stream".to_vec()));
+        assert_eq!(fetch(&manager).unwrap(), Some(b"stream".to_vec()));
+    }
+
+    #[test]
+    fn streaming_falls_back_to_buffered_providers() {
+        let miss = Probe::new(Response::Miss);
+        let hit = Probe::new(Response::Hit);
+
+        let manager = manager(FetchStrategy::Sequential, &[&miss, &hit]);
+
+        assert_eq!(fetch_stream(&manager).unwrap(), Some(b"data".to_vec()));
+        assert_eq!((miss.calls(), hit.calls()), (1, 1));
+    }
+
+    fn publish(manager: &SimpleResourceManager) -> Result<(), ResourceError<Infallible>> {
+        block_on(ResourceManager::publish(
+            manager,
+            TypeId::of::<Sha256>(),
+            Box::new(|| Box::new(Sha256Sum([0; 32])) as Box<dyn Any + Send>),
+            b"data".to_vec(),
+        ))
+    }
+
+    #[test]
+    fn publish_without_stores_fails() {
+        let manager = SimpleResourceManager::new();
+
+        assert!(matches!(publish(&manager), Err(ResourceError::NoStore)));
+    }
+
+    #[test]
+    fn publish_skips_read_only_stores() {
+        let (writable, read_only) = (MemoryStore::<Sha256>::new(), MemoryStore::<Sha256>::new());
+        let mut manager = SimpleResourceManager::new();
+
+        let mut handle = block_on(StoreRegistrant::<Sha256, _>::register_store(
+            &mut manager,
+            writable.clone(),
+            ProviderMetadata::named("writable"),
+        ))
+        .unwrap();
+        block_on(StoreRegistrant::<Sha256, _>::register_store(
+            &mut manager,
+            read_only.clone(),
+            ProviderMetadata::named("read-only").read_only(true),
+        ))
+        .unwrap();
+
+        assert_eq!(block_on(manager.stores::<Sha256>()).len(), 2);
+
+        publish(&manager).unwrap();
+
+        assert!(block_on(writable.contains(Sha256Sum([0; 32]))).unwrap());
+        assert!(!block_on(read_only.contains(Sha256Sum([0; 32]))).unwrap());
+
+        block_on(handle.unregister()).unwrap();
+
+        assert!(matches!(publish(&manager), Err(ResourceError::NoStore)));
+
+        assert_eq!(
+            block_on(manager.stores::<Sha256>()),
+            vec![ProviderMetadata::named("read-only").read_only(true)]
+        );
     }
 }
@@ -0,0 +1,66 @@
+use crate::{resource::hash::Hasher, Sha256, Sha256Sum};
+use std::{
+    env, fs,
+    path::PathBuf,
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use thiserror::Error;
+
+pub struct TestHasher {
+    lanes: [u64; 4],
+    len: u64,
+}
+
+impl Hasher<Sha256> for TestHasher {
+    fn new() -> Self {
+        TestHasher {
+            lanes: [
+                0xcbf2_9ce4_8422_2325,
+                0x84222325_cbf29ce4,
+                0x9e37_79b9_7f4a_7c15,
+                0xc2b2_ae3d_27d4_eb4f,
+            ],
+            len: 0,
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) {
+        for byte in data {
+            for (idx, lane) in self.lanes.iter_mut().enumerate() {
+                *lane ^= u64::from(*byte).wrapping_add(idx as u64);
+                *lane = lane.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+        self.len += data.len() as u64;
+    }
+
+    fn hash(&self) -> Sha256Sum {
+        let mut sum = [0u8; 32];
+
+        for (idx, lane) in self.lanes.iter().enumerate() {
+            let lane = lane ^ self.len.rotate_left(idx as u32 * 16);
+            sum[idx * 8..idx * 8 + 8].copy_from_slice(&lane.to_be_bytes());
+        }
+
+        Sha256Sum(sum)
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("test error")]
+pub struct TestError;
+
+pub fn temp_dir(name: &str) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let path = env::temp_dir().join(format!(
+        "vessels-{}-{}-{}",
+        name,
+        process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let _ = fs::remove_dir_all(&path);
+    fs::create_dir_all(&path).unwrap();
+    path
+}
@@ -0,0 +1,439 @@
+use crate::resource::{
+    hash::Algorithm,
+    provider::{ErrorErasedResourceProvider, ResourceProvider, ResourceProviderExt},
+    store::{ErrorErasedResourceStore, ResourceStore, ResourceStoreExt},
+};
+use core_error::Error;
+use futures::{future::try_join_all, Future, TryFuture};
+use std::{
+    collections::HashSet,
+    hash::Hash,
+    mem::replace,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TieredStoreError {
+    #[error("no writable tier")]
+    NoWritableTier,
+    #[error("dirty resource is missing from the first writable tier")]
+    MissingDirty,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WritePolicy {
+    Through,
+    Back,
+}
+
+struct Tier<A: Algorithm> {
+    provider: Mutex<ErrorErasedResourceProvider<A>>,
+    store: Option<ErrorErasedResourceStore<A>>,
+}
+
+impl<A: Algorithm> Tier<A> {
+    fn fetch(
+        &self,
+        hash: A::Hash,
+    ) -> Pin<Box<dyn Future<Output = Result<Option<Vec<u8>>, Box<dyn Error + Send>>> + Send>> {
+        self.provider.lock().unwrap().fetch(hash)
+    }
+}
+
+fn puts<A: Algorithm>(
+    tiers: &[Tier<A>],
+    hash: &A::Hash,
+    data: &[u8],
+) -> Vec<Pin<Box<dyn Future<Output = Result<(), Box<dyn Error + Send>>> + Send>>>
+where
+    A::Hash: Clone,
+{
+    tiers
+        .iter()
+        .filter_map(|tier| tier.store.as_ref())
+        .map(|store| store.put_raw(hash.clone(), data.to_vec()))
+        .collect()
+}
+
+fn first_writable<A: Algorithm>(tiers: &[Tier<A>]) -> Option<usize> {
+    tiers.iter().position(|tier| tier.store.is_some())
+}
+
+pub struct TieredStoreBuilder<A: Algorithm> {
+    tiers: Vec<Tier<A>>,
+    policy: WritePolicy,
+}
+
+impl<A: Algorithm + Send + 'static> TieredStoreBuilder<A>
+where
+    A::Hash: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    pub fn tier<T>(mut self, layer: T) -> Self
+    where
+        T: ResourceProvider<A> + ResourceStore<A> + Clone + Send + Sync + 'static,
+        <T as ResourceProvider<A>>::Fetch: Unpin + Send + 'static,
+        <<T as ResourceProvider<A>>::Fetch as TryFuture>::Error: Error + Send,
+        T::Put: Send + 'static,
+        T::Remove: Send + 'static,
+        T::Contains: Send + 'static,
+        <T::Put as TryFuture>::Error: Error + Send + 'static,
+        <T::Remove as TryFuture>::Error: Error + Send + 'static,
+        <T::Contains as TryFuture>::Error: Error + Send + 'static,
+        A: Sync,
+    {
+        self.tiers.push(Tier {
+            provider: Mutex::new(layer.clone().erase()),
+            store: Some(layer.erase_store()),
+        });
+        self
+    }
+
+    pub fn read_only_tier<T>(mut self, provider: T) -> Self
+    where
+        T: ResourceProvider<A> + Send + 'static,
+        T::Fetch: Unpin + Send + 'static,
+        <T::Fetch as TryFuture>::Error: Error + Send,
+    {
+        self.tiers.push(Tier {
+            provider: Mutex::new(provider.erase()),
+            store: None,
+        });
+        self
+    }
+
+    pub fn build(self) -> TieredStore<A> {
+        TieredStore {
+            tiers: self.tiers.into(),
+            dirty: Arc::new(Mutex::new(HashSet::new())),
+            policy: self.policy,
+        }
+    }
+}
+
+pub struct TieredStore<A: Algorithm> {
+    tiers: Arc<[Tier<A>]>,
+    dirty: Arc<Mutex<HashSet<A::Hash>>>,
+    policy: WritePolicy,
+}
+
+impl<A: Algorithm> Clone for TieredStore<A> {
+    fn clone(&self) -> Self {
+        TieredStore {
+            tiers: self.tiers.clone(),
+            dirty: self.dirty.clone(),
+            policy: self.policy,
+        }
+    }
+}
+
+impl<A: Algorithm + Send + 'static> TieredStore<A>
+where
+    A::Hash: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    pub fn builder(policy: WritePolicy) -> TieredStoreBuilder<A> {
+        TieredStoreBuilder {
+            tiers: vec![],
+            policy,
+        }
+    }
+
+    pub fn dirty(&self) -> usize {
+        self.dirty.lock().unwrap().len()
+    }
+
+    pub fn flush(&self) -> impl Future<Output = Result<(), Box<dyn Error + Send>>> {
+        let (tiers, dirty) = (self.tiers.clone(), self.dirty.clone());
+
+        async move {
+            let pending = replace(&mut *dirty.lock().unwrap(), HashSet::new());
+            let first = match first_writable(&tiers) {
+                Some(first) => first,
+                None => return Ok(()),
+            };
+
+            let mut entries = pending.into_iter();
+
+            while let Some(hash) = entries.next() {
+                let result = async {
+                    let data = tiers[first].fetch(hash.clone()).await?.ok_or_else(|| {
+                        Box::new(TieredStoreError::MissingDirty) as Box<dyn Error + Send>
+                    })?;
+
+                    try_join_all(puts(&tiers[first + 1..], &hash, &data)).await?;
+
+                    Ok::<_, Box<dyn Error + Send>>(())
+                }
+                .await;
+
+                if let Err(e) = result {
+                    let mut dirty = dirty.lock().unwrap();
+                    dirty.insert(hash);
+                    dirty.extend(entries);
+                    return Err(e);
+                }
+            }
+
+            Ok(())
+        }
+    }
+}
+
+impl<A: Algorithm> ResourceProvider<A> for TieredStore<A>
+where
+    A::Hash: Hash + Eq + Clone + Send + Sync + 'static,
+    A: Send + Sync + 'static,
+{
+    type Fetch =
+        Pin<Box<dyn Future<Output = Result<Option<Vec<u8>>, Box<dyn Error + Send>>> + Send>>;
+
+    fn fetch(&self, hash: A::Hash) -> Self::Fetch {
+        let tiers = self.tiers.clone();
+
+        Box::pin(async move {
+            for (idx, tier) in tiers.iter().enumerate() {
+                if let Some(data) = tier.fetch(hash.clone()).await? {
+                    let _ = try_join_all(puts(&tiers[..idx], &hash, &data)).await;
+
+                    return Ok(Some(data));
+                }
+            }
+
+            Ok(None)
+        })
+    }
+}
+
+impl<A: Algorithm> ResourceStore<A> for TieredStore<A>
+where
+    A::Hash: Hash + Eq + Clone + Send + Sync + 'static,
+    A: Send + Sync + 'static,
+{
+    type Put = Pin<Box<dyn Future<Output = Result<(), Box<dyn Error + Send>>> + Send>>;
+    type Remove = Pin<Box<dyn Future<Output = Result<bool, Box<dyn Error + Send>>> + Send>>;
+    type Contains = Pin<Box<dyn Future<Output = Result<bool, Box<dyn Error + Send>>> + Send>>;
+
+    fn put_raw(&self, hash: A::Hash, data: Vec<u8>) -> Self::Put {
+        let (tiers, dirty, policy) = (self.tiers.clone(), self.dirty.clone(), self.policy);
+
+        Box::pin(async move {
+            let first = first_writable(&tiers).ok_or_else(|| {
+                Box::new(TieredStoreError::NoWritableTier) as Box<dyn Error + Send>
+            })?;
+
+            match policy {
+                WritePolicy::Through => {
+                    try_join_all(puts(&tiers[first..], &hash, &data)).await?;
+                }
+                WritePolicy::Back => {
+                    try_join_all(puts(&tiers[first..first + 1], &hash, &data)).await?;
+                    dirty.lock().unwrap().insert(hash);
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    fn remove(&self, hash: A::Hash) -> Self::Remove {
+        self.dirty.lock().unwrap().remove(&hash);
+
+        let removes = self
+            .tiers
+            .iter()
+            .filter_map(|tier| tier.store.as_ref())
+            .map(|store| store.remove(hash.clone()))
+            .collect::<Vec<_>>();
+
+        Box::pin(async move {
+            let mut removed = false;
+
+            for result in try_join_all(removes).await? {
+                removed |= result;
+            }
+
+            Ok(removed)
+        })
+    }
+
+    fn contains(&self, hash: A::Hash) -> Self::Contains {
+        let tiers = self.tiers.clone();
+
+        Box::pin(async move {
+            for tier in tiers.iter() {
+                let found = match &tier.store {
+                    Some(store) => store.contains(hash.clone()).await?,
+                    None => tier.fetch(hash.clone()).await?.is_some(),
+                };
+
+                if found {
+                    return Ok(true);
+                }
+            }
+
+            Ok(false)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_util::TestError, MemoryStore, Sha256, Sha256Sum};
+    use futures::{
+        executor::block_on,
+        future::{ready, Ready},
+    };
+
+    #[derive(Clone)]
+    struct Unwritable;
+
+    impl ResourceProvider<Sha256> for Unwritable {
+        type Fetch = Ready<Result<Option<Vec<u8>>, TestError>>;
+
+        fn fetch(&self, _: Sha256Sum) -> Self::Fetch {
+            ready(Ok(None))
+        }
+    }
+
+    impl ResourceStore<Sha256> for Unwritable {
+        type Put = Ready<Result<(), TestError>>;
+        type Remove = Ready<Result<bool, TestError>>;
+        type Contains = Ready<Result<bool, TestError>>;
+
+        fn put_raw(&self, _: Sha256Sum, _: Vec<u8>) -> Self::Put {
+            ready(Err(TestError))
+        }
+
+        fn remove(&self, _: Sha256Sum) -> Self::Remove {
+            ready(Ok(false))
+        }
+
+        fn contains(&self, _: Sha256Sum) -> Self::Contains {
+            ready(Ok(false))
+        }
+    }
+
+    const HASH: Sha256Sum = Sha256Sum([1; 32]);
+
+    #[test]
+    fn put_without_writable_tier_fails() {
+        for policy in &[WritePolicy::Through, WritePolicy::Back] {
+            let store = TieredStore::<Sha256>::builder(*policy)
+                .read_only_tier(MemoryStore::new())
+                .build();
+
+            assert!(block_on(store.put_raw(HASH, b"data".to_vec())).is_err());
+        }
+    }
+
+    #[test]
+    fn write_through_fills_every_tier() {
+        let (near, far) = (MemoryStore::<Sha256>::new(), MemoryStore::new());
+        let store = TieredStore::builder(WritePolicy::Through)
+            .tier(near.clone())
+            .tier(far.clone())
+            .build();
+
+        block_on(store.put_raw(HASH, b"data".to_vec())).unwrap();
+
+        assert!(block_on(near.contains(HASH)).unwrap());
+        assert!(block_on(far.contains(HASH)).unwrap());
+        assert_eq!(store.dirty(), 0);
+    }
+
+    #[test]
+    fn write_back_flushes_from_first_tier() {
+        let (near, far) = (MemoryStore::<Sha256>::new(), MemoryStore::new());
+        let store = TieredStore::builder(WritePolicy::Back)
+            .tier(near.clone())
+            .tier(far.clone())
+            .build();
+
+        block_on(store.put_raw(HASH, b"data".to_vec())).unwrap();
+
+        assert!(block_on(near.contains(HASH)).unwrap());
+        assert!(!block_on(far.contains(HASH)).unwrap());
+        assert_eq!(store.dirty(), 1);
+
+        block_on(store.flush()).unwrap();
+
+        assert_eq!(block_on(far.fetch(HASH)).unwrap(), Some(b"data".to_vec()));
+        assert_eq!(store.dirty(), 0);
+    }
+
+    #[test]
+    fn fetch_promotes_into_nearer_tiers() {
+        let (near, far) = (MemoryStore::<Sha256>::new(), MemoryStore::new());
+        let store = TieredStore::builder(WritePolicy::Through)
+            .tier(near.clone())
+            .tier(far.clone())
+            .build();
+
+        block_on(far.put_raw(HASH, b"data".to_vec())).unwrap();
+
+        assert_eq!(block_on(store.fetch(HASH)).unwrap(), Some(b"data".to_vec()));
+        assert!(block_on(near.contains(HASH)).unwrap());
+    }
+
+    #[test]
+    fn failed_flush_keeps_hashes_dirty() {
+        let store = TieredStore::builder(WritePolicy::Back)
+            .tier(MemoryStore::<Sha256>::new())
+            .tier(Unwritable)
+            .build();
+
+        block_on(store.put_raw(HASH, b"data".to_vec())).unwrap();
+
+        assert!(block_on(store.flush()).is_err());
+        assert_eq!(store.dirty(), 1);
+    }
+
+    #[test]
+    fn flush_of_a_missing_dirty_hash_fails() {
+        let (near, far) = (MemoryStore::<Sha256>::new(), MemoryStore::new());
+        let store = TieredStore::builder(WritePolicy::Back)
+            .tier(near.clone())
+            .tier(far.clone())
+            .build();
+
+        block_on(store.put_raw(HASH, b"data".to_vec())).unwrap();
+        block_on(near.remove(HASH)).unwrap();
+
+        assert!(block_on(store.flush()).is_err());
+        assert_eq!(store.dirty(), 1);
+        assert!(!block_on(far.contains(HASH)).unwrap());
+    }
+
+    #[test]
+    fn read_only_tier_between_writable_tiers() {
+        let (near, middle, far) = (
+            MemoryStore::<Sha256>::new(),
+            MemoryStore::new(),
+            MemoryStore::new(),
+        );
+        let store = TieredStore::builder(WritePolicy::Back)
+            .tier(near.clone())
+            .read_only_tier(middle.clone())
+            .tier(far.clone())
+            .build();
+
+        block_on(store.put_raw(HASH, b"data".to_vec())).unwrap();
+        block_on(store.flush()).unwrap();
+
+        assert!(block_on(far.contains(HASH)).unwrap());
+        assert!(!block_on(middle.contains(HASH)).unwrap());
+
+        let other = Sha256Sum([2; 32]);
+        block_on(middle.put_raw(other, b"other".to_vec())).unwrap();
+
+        assert_eq!(
+            block_on(store.fetch(other)).unwrap(),
+            Some(b"other".to_vec())
+        );
+        assert!(block_on(near.contains(other)).unwrap());
+        assert!(!block_on(far.contains(other)).unwrap());
+        assert!(block_on(store.contains(other)).unwrap());
+    }
+}
@@ -0,0 +1,275 @@
+use crate::resource::Rehydrate;
+use core::marker::PhantomData;
+use core_error::Error;
+use futures::{Future, TryFutureExt};
+use std::pin::Pin;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[bounds(where T: Error + 'static)]
+pub enum VersionedError<T> {
+    #[error("missing version header")]
+    NoHeader,
+    #[error("unknown future version {0}")]
+    FutureVersion(u32),
+    #[error("no migration from version {0}")]
+    MissingMigration(u32),
+    #[error("migration error: {0}")]
+    Migration(#[source] Box<dyn Error + Send>),
+    #[error("inner error: {0}")]
+    Inner(#[source] T),
+}
+
+pub struct Migration {
+    from: u32,
+    step: Box<
+        dyn Fn(
+                Vec<u8>,
+            )
+                -> Pin<Box<dyn Future<Output = Result<Vec<u8>, Box<dyn Error + Send>>> + Send>>
+            + Send
+            + Sync,
+    >,
+}
+
+impl Migration {
+    pub fn new<U, T, V>(from: u32, migrate: fn(T) -> V) -> Self
+    where
+        U: Rehydrate<T> + Rehydrate<V>,
+        <U as Rehydrate<T>>::Rehydrate: Send + 'static,
+        <U as Rehydrate<T>>::RehydrateError: Error + Send + 'static,
+        <U as Rehydrate<V>>::Dump: Send + 'static,
+        <U as Rehydrate<V>>::DumpError: Error + Send + 'static,
+        T: 'static,
+        V: 'static,
+    {
+        Migration {
+            from,
+            step: Box::new(move |data| {
+                let item = <U as Rehydrate<T>>::rehydrate(data);
+
+                Box::pin(async move {
+                    let item = item
+                        .map_err(|e| Box::new(e) as Box<dyn Error + Send>)
+                        .await?;
+
+                    <U as Rehydrate<V>>::dump((migrate)(item))
+                        .map_err(|e| Box::new(e) as Box<dyn Error + Send>)
+                        .await
+                })
+            }),
+        }
+    }
+}
+
+pub trait Schema: Sized {
+    const VERSION: u32;
+
+    fn migrations() -> Vec<Migration>;
+}
+
+pub struct Versioned<U>(PhantomData<U>);
+
+impl<T, U> Rehydrate<T> for Versioned<U>
+where
+    T: Schema + Send + 'static,
+    U: Rehydrate<T>,
+    U::Rehydrate: Send + 'static,
+    U::RehydrateError: Send + 'static,
+    U::Dump: Send + 'static,
+    U::DumpError: Send + 'static,
+{
+    type RehydrateError = VersionedError<U::RehydrateError>;
+    type Rehydrate = Pin<Box<dyn Future<Output = Result<T, Self::RehydrateError>> + Send>>;
+    type DumpError = VersionedError<U::DumpError>;
+    type Dump = Pin<Box<dyn Future<Output = Result<Vec<u8>, Self::DumpError>> + Send>>;
+
+    fn rehydrate(mut data: Vec<u8>) -> Self::Rehydrate {
+        Box::pin(async move {
+            if data.len() < 4 {
+                return Err(VersionedError::NoHeader);
+            }
+
+            let body = data.split_off(4);
+            let mut version = [0u8; 4];
+            version.copy_from_slice(&data);
+            let mut version = u32::from_be_bytes(version);
+            let mut data = body;
+
+            if version > T::VERSION {
+                return Err(VersionedError::FutureVersion(version));
+            }
+
+            let migrations = T::migrations();
+
+            while version < T::VERSION {
+                let migration = migrations
+                    .iter()
+                    .find(|migration| migration.from == version)
+                    .ok_or(VersionedError::MissingMigration(version))?;
+
+                data = (migration.step)(data)
+                    .await
+                    .map_err(VersionedError::Migration)?;
+
+                version += 1;
+            }
+
+            U::rehydrate(data).await.map_err(VersionedError::Inner)
+        })
+    }
+
+    fn dump(data: T) -> Self::Dump {
+        let item = U::dump(data);
+
+        Box::pin(async move {
+            let item = item.await.map_err(VersionedError::Inner)?;
+
+            let mut data = Vec::with_capacity(item.len() + 4);
+            data.extend_from_slice(&T::VERSION.to_be_bytes());
+            data.extend(item);
+
+            Ok(data)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cbor;
+    use futures::executor::block_on;
+    use serde::{Deserialize, Serialize};
+    use serde_cbor::to_vec;
+
+    #[derive(Serialize, Deserialize)]
+    struct V0 {
+        name: String,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct V1 {
+        name: String,
+        age: u32,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct V2 {
+        first: String,
+        age: u32,
+        tagged: bool,
+    }
+
+    impl Schema for V2 {
+        const VERSION: u32 = 2;
+
+        fn migrations() -> Vec<Migration> {
+            vec![
+                Migration::new::<Cbor, V0, V1>(0, |item| V1 {
+                    name: item.name,
+                    age: 0,
+                }),
+                Migration::new::<Cbor, V1, V2>(1, |item| V2 {
+                    first: item.name,
+                    age: item.age,
+                    tagged: false,
+                }),
+            ]
+        }
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Gapped;
+
+    impl Schema for Gapped {
+        const VERSION: u32 = 2;
+
+        fn migrations() -> Vec<Migration> {
+            vec![]
+        }
+    }
+
+    fn encode<T: Serialize>(version: u32, item: &T) -> Vec<u8> {
+        let mut data = version.to_be_bytes().to_vec();
+        data.extend(to_vec(item).unwrap());
+        data
+    }
+
+    fn rehydrate<T>(data: Vec<u8>) -> Result<T, VersionedError<serde_cbor::Error>>
+    where
+        T: Schema + Serialize + for<'de> Deserialize<'de> + Send + 'static,
+    {
+        block_on(<Versioned<Cbor> as Rehydrate<T>>::rehydrate(data))
+    }
+
+    #[test]
+    fn migrates_through_chain() {
+        let data = encode(
+            0,
+            &V0 {
+                name: "vessel".to_owned(),
+            },
+        );
+
+        assert_eq!(
+            rehydrate::<V2>(data).unwrap(),
+            V2 {
+                first: "vessel".to_owned(),
+                age: 0,
+                tagged: false,
+            }
+        );
+    }
+
+    #[test]
+    fn current_version_round_trips() {
+        let item = V2 {
+            first: "vessel".to_owned(),
+            age: 3,
+            tagged: true,
+        };
+
+        let data = block_on(<Versioned<Cbor> as Rehydrate<V2>>::dump(item)).unwrap();
+
+        assert_eq!(&data[..4], &2u32.to_be_bytes());
+        assert_eq!(
+            rehydrate::<V2>(data).unwrap(),
+            V2 {
+                first: "vessel".to_owned(),
+                age: 3,
+                tagged: true,
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_future_version() {
+        let data = encode(
+            3,
+            &V0 {
+                name: "vessel".to_owned(),
+            },
+        );
+
+        assert!(matches!(
+            rehydrate::<V2>(data),
+            Err(VersionedError::FutureVersion(3))
+        ));
+    }
+
+    #[test]
+    fn reports_missing_migration() {
+        assert!(matches!(
+            rehydrate::<Gapped>(encode(1, &Gapped)),
+            Err(VersionedError::MissingMigration(1))
+        ));
+    }
+
+    #[test]
+    fn rejects_missing_header() {
+        assert!(matches!(
+            rehydrate::<V2>(vec![0, 0]),
+            Err(VersionedError::NoHeader)
+        ));
+    }
+}